@@ -1,25 +1,39 @@
 use super::Renderer;
 use cgmath::{Deg, Matrix4, Point3, Vector3};
 
+const FIELD_OF_VIEW: Deg<f32> = Deg(60.0);
+const NEAR: f32 = 0.1;
+const FAR: f32 = 1000.0;
+
 pub struct Camera {
     position: Point3<f32>,
     forward: Vector3<f32>,
+    aspect_ratio: f32,
     projection: Matrix4<f32>,
 }
 
 impl Camera {
     pub fn new(renderer: &Renderer, position: Point3<f32>, forward: Vector3<f32>) -> Self {
-        let logical_size = renderer.window.get_inner_size().unwrap();
+        let logical_size = renderer.get_size();
         let aspect_ratio = logical_size.width as f32 / logical_size.height as f32;
-        let projection = cgmath::perspective(Deg(60.0f32), aspect_ratio, 0.1, 1000.0);
+        let projection = cgmath::perspective(FIELD_OF_VIEW, aspect_ratio, NEAR, FAR);
 
         Self {
             position,
             forward,
+            aspect_ratio,
             projection,
         }
     }
 
+    pub fn get_position(&self) -> Point3<f32> {
+        self.position
+    }
+
+    pub fn get_forward(&self) -> Vector3<f32> {
+        self.forward
+    }
+
     pub fn get_view(&self) -> Matrix4<f32> {
         Matrix4::look_at(
             self.position,
@@ -31,4 +45,20 @@ impl Camera {
     pub fn get_projection(&self) -> &Matrix4<f32> {
         &self.projection
     }
+
+    pub fn get_field_of_view(&self) -> Deg<f32> {
+        FIELD_OF_VIEW
+    }
+
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    pub fn get_near(&self) -> f32 {
+        NEAR
+    }
+
+    pub fn get_far(&self) -> f32 {
+        FAR
+    }
 }
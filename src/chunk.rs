@@ -0,0 +1,291 @@
+use super::Vertex;
+
+pub const CHUNK_SIZE: usize = 16;
+
+pub type BlockId = u8;
+pub const AIR: BlockId = 0;
+
+/// Cubic grid of blocks, `AIR` being empty space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    size: usize,
+    blocks: Vec<BlockId>,
+}
+
+impl Chunk {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            blocks: vec![AIR; size * size * size],
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.size + y) * self.size + x
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockId {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockId) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+    }
+
+    // NOTE: Out of bounds blocks are considered empty, so chunk borders are always meshed.
+    pub fn is_solid(&self, x: isize, y: isize, z: isize) -> bool {
+        let size = self.size as isize;
+
+        x >= 0
+            && y >= 0
+            && z >= 0
+            && x < size
+            && y < size
+            && z < size
+            && self.get(x as usize, y as usize, z as usize) != AIR
+    }
+}
+
+pub fn block_color(block: BlockId) -> [f32; 3] {
+    match block {
+        1 => [0.3, 0.7, 0.2],
+        2 => [0.5, 0.35, 0.2],
+        3 => [0.5, 0.5, 0.5],
+        4 => [0.9, 0.85, 0.6],
+        _ => [1.0, 0.0, 1.0],
+    }
+}
+
+pub(crate) struct Face {
+    pub(crate) normal: [isize; 3],
+    pub(crate) corners: [[i8; 3]; 4],
+}
+
+// NOTE: Unit cube faces ordered +z, -z, +x, -x, +y, -y, shared with the box builder.
+pub(crate) const FACES: [Face; 6] = [
+    Face {
+        normal: [0, 0, 1],
+        corners: [[0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]],
+    },
+    Face {
+        normal: [0, 0, -1],
+        corners: [[0, 1, 0], [1, 1, 0], [1, 0, 0], [0, 0, 0]],
+    },
+    Face {
+        normal: [1, 0, 0],
+        corners: [[1, 0, 0], [1, 1, 0], [1, 1, 1], [1, 0, 1]],
+    },
+    Face {
+        normal: [-1, 0, 0],
+        corners: [[0, 0, 1], [0, 1, 1], [0, 1, 0], [0, 0, 0]],
+    },
+    Face {
+        normal: [0, 1, 0],
+        corners: [[1, 1, 0], [0, 1, 0], [0, 1, 1], [1, 1, 1]],
+    },
+    Face {
+        normal: [0, -1, 0],
+        corners: [[1, 0, 1], [0, 0, 1], [0, 0, 0], [1, 0, 0]],
+    },
+];
+
+/// Visible face of a block, as found by `visit_faces`.
+pub struct VisibleFace {
+    pub block: BlockId,
+    // Index into the face table, the same in the packed vertex shader.
+    pub normal_index: u8,
+    // Corner positions in blocks, skirts reaching one block below the chunk.
+    pub corners: [[i32; 3]; 4],
+    // Ambient occlusion of each corner, from 0 (fully occluded) to 3.
+    pub ambient_occlusion: [u8; 4],
+}
+
+fn corner_ambient_occlusion(
+    chunk: &Chunk,
+    block: [isize; 3],
+    normal: [isize; 3],
+    corner: [i8; 3],
+) -> u8 {
+    let mut sides = [block; 2];
+    let mut diagonal = block;
+    let mut side = 0;
+
+    for axis in 0..3 {
+        diagonal[axis] += normal[axis];
+        sides[0][axis] += normal[axis];
+        sides[1][axis] += normal[axis];
+
+        if normal[axis] == 0 {
+            let direction = if corner[axis] == 1 { 1 } else { -1 };
+
+            diagonal[axis] += direction;
+            sides[side][axis] += direction;
+            side += 1;
+        }
+    }
+
+    let side1 = chunk.is_solid(sides[0][0], sides[0][1], sides[0][2]);
+    let side2 = chunk.is_solid(sides[1][0], sides[1][1], sides[1][2]);
+    let corner = chunk.is_solid(diagonal[0], diagonal[1], diagonal[2]);
+
+    if side1 && side2 {
+        0
+    } else {
+        3 - side1 as u8 - side2 as u8 - corner as u8
+    }
+}
+
+/// Calls `visit` with every visible face of `chunk`.
+///
+/// With `skirts`, side faces on the chunk border are stretched down by one
+/// block, hiding the cracks between neighbouring chunks of different LODs.
+pub fn visit_faces<F: FnMut(&VisibleFace)>(chunk: &Chunk, skirts: bool, mut visit: F) {
+    let size = chunk.get_size() as isize;
+
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                if !chunk.is_solid(x, y, z) {
+                    continue;
+                }
+
+                let block = chunk.get(x as usize, y as usize, z as usize);
+
+                for (normal_index, face) in FACES.iter().enumerate() {
+                    let neighbour = [x + face.normal[0], y + face.normal[1], z + face.normal[2]];
+
+                    if chunk.is_solid(neighbour[0], neighbour[1], neighbour[2]) {
+                        continue;
+                    }
+
+                    let on_border = neighbour[0] < 0
+                        || neighbour[0] >= size
+                        || neighbour[2] < 0
+                        || neighbour[2] >= size;
+                    let mut visible_face = VisibleFace {
+                        block,
+                        normal_index: normal_index as u8,
+                        corners: [[0; 3]; 4],
+                        ambient_occlusion: [0; 4],
+                    };
+
+                    for (i, corner) in face.corners.iter().enumerate() {
+                        let mut position = [
+                            x as i32 + i32::from(corner[0]),
+                            y as i32 + i32::from(corner[1]),
+                            z as i32 + i32::from(corner[2]),
+                        ];
+
+                        if skirts && on_border && corner[1] == 0 {
+                            position[1] -= 1;
+                        }
+
+                        visible_face.corners[i] = position;
+                        visible_face.ambient_occlusion[i] =
+                            corner_ambient_occlusion(chunk, [x, y, z], face.normal, *corner);
+                    }
+
+                    visit(&visible_face);
+                }
+            }
+        }
+    }
+}
+
+/// Appends the two triangles of the quad whose first vertex is `first_index`.
+pub fn push_quad_indices(indices: &mut Vec<u32>, first_index: u32) {
+    indices.extend_from_slice(&[
+        first_index,
+        first_index + 1,
+        first_index + 2,
+        first_index + 2,
+        first_index + 3,
+        first_index,
+    ]);
+}
+
+/// Builds the visible faces of `chunk`, each block being `scale` units wide.
+pub fn build_chunk_mesh(chunk: &Chunk, scale: f32, skirts: bool) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    visit_faces(chunk, skirts, |face| {
+        let color = block_color(face.block);
+
+        push_quad_indices(&mut indices, vertices.len() as u32);
+
+        for corner in &face.corners {
+            let position = [
+                corner[0] as f32 * scale,
+                corner[1] as f32 * scale,
+                corner[2] as f32 * scale,
+            ];
+
+            vertices.push(Vertex::new(position, color));
+        }
+    });
+
+    (vertices, indices)
+}
+
+#[test]
+fn test_single_block_mesh() {
+    let mut chunk = Chunk::new(4);
+    chunk.set(1, 1, 1, 1);
+
+    let (vertices, indices) = build_chunk_mesh(&chunk, 1.0, false);
+
+    assert_eq!(vertices.len(), 6 * 4);
+    assert_eq!(indices.len(), 6 * 6);
+}
+
+#[test]
+fn test_hidden_faces_are_culled() {
+    let mut chunk = Chunk::new(4);
+    chunk.set(1, 1, 1, 1);
+    chunk.set(2, 1, 1, 1);
+
+    let (vertices, _) = build_chunk_mesh(&chunk, 1.0, false);
+
+    assert_eq!(vertices.len(), 10 * 4);
+}
+
+#[test]
+fn test_large_chunk_mesh() {
+    let mut chunk = Chunk::new(130);
+    chunk.set(129, 129, 129, 1);
+
+    let (vertices, _) = build_chunk_mesh(&chunk, 8.0, false);
+    let max_x = vertices
+        .iter()
+        .map(|vertex| vertex.pos[0])
+        .fold(0.0, f32::max);
+
+    // Positions past 127 blocks neither wrap nor overflow.
+    assert_eq!(vertices.len(), 6 * 4);
+    assert_eq!(max_x, 130.0 * 8.0);
+    assert!(vertices.iter().all(|vertex| vertex.pos[1] >= 129.0 * 8.0));
+}
+
+#[test]
+fn test_corner_ambient_occlusion() {
+    let mut chunk = Chunk::new(4);
+    chunk.set(1, 1, 1, 1);
+    chunk.set(2, 2, 1, 1);
+
+    let mut top_occlusion = Vec::new();
+    visit_faces(&chunk, false, |face| {
+        if face.normal_index == 4 && face.corners[0][1] == 2 {
+            top_occlusion.extend_from_slice(&face.ambient_occlusion);
+        }
+    });
+
+    // The top face of the lower block has its +x corners darkened by the block beside it.
+    assert_eq!(top_occlusion, vec![2, 3, 3, 2]);
+}
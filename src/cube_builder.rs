@@ -1,52 +1,155 @@
-use super::{VoxelMesh, Renderer, Transform, Vertex};
+use super::chunk::FACES;
+use super::{push_quad_indices, Renderer, Transform, Vertex, VoxelMesh};
 use cgmath::Vector3;
 
-pub fn cube_mesh_builder(renderer: &mut Renderer, position: Vector3<f32>, color: [f32; 3]) -> VoxelMesh {
-    let vertices: Vec<Vertex> = vec![
-        // Top (0, 0, 1)
-        Vertex::new([-1, -1, 1], color),
-        Vertex::new([1, -1, 1], color),
-        Vertex::new([1, 1, 1], color),
-        Vertex::new([-1, 1, 1], color),
-        // Bottom (0, 0, -1)
-        Vertex::new([-1, 1, -1], color),
-        Vertex::new([1, 1, -1], color),
-        Vertex::new([1, -1, -1], color),
-        Vertex::new([-1, -1, -1], color),
-        // Right (1, 0, 0)
-        Vertex::new([1, -1, -1], color),
-        Vertex::new([1, 1, -1], color),
-        Vertex::new([1, 1, 1], color),
-        Vertex::new([1, -1, 1], color),
-        // Left (-1, 0, 0)
-        Vertex::new([-1, -1, 1], color),
-        Vertex::new([-1, 1, 1], color),
-        Vertex::new([-1, 1, -1], color),
-        Vertex::new([-1, -1, -1], color),
-        // Front (0, 1, 0)
-        Vertex::new([1, 1, -1], color),
-        Vertex::new([-1, 1, -1], color),
-        Vertex::new([-1, 1, 1], color),
-        Vertex::new([1, 1, 1], color),
-        // Back (0, -1, 0)
-        Vertex::new([1, -1, 1], color),
-        Vertex::new([-1, -1, 1], color),
-        Vertex::new([-1, -1, -1], color),
-        Vertex::new([1, -1, -1], color),
-    ];
-
-    let indices: Vec<u16> = vec![
-        0, 1, 2, 2, 3, 0, // Top
-        4, 5, 6, 6, 7, 4, // Bottom
-        8, 9, 10, 10, 11, 8, // Right
-        12, 13, 14, 14, 15, 12, // Left
-        16, 17, 18, 18, 19, 16, // Front
-        20, 21, 22, 22, 23, 20, // Back
-    ];
-
-    let up = Vector3::unit_y();
-    let forward = Vector3::unit_z();
-    let model = Transform::new(position, up, forward).get_transform();
-
-    VoxelMesh::new(renderer, &vertices, &indices, model)
+/// Side of a box, in the same order as the chunk mesher faces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoxFace {
+    // +z
+    Front,
+    // -z
+    Back,
+    // +x
+    Right,
+    // -x
+    Left,
+    // +y
+    Top,
+    // -y
+    Bottom,
+}
+
+pub const BOX_FACES: [BoxFace; 6] = [
+    BoxFace::Front,
+    BoxFace::Back,
+    BoxFace::Right,
+    BoxFace::Left,
+    BoxFace::Top,
+    BoxFace::Bottom,
+];
+
+/// Set of box faces to build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FaceMask(u8);
+
+impl FaceMask {
+    pub const ALL: FaceMask = FaceMask(0b11_1111);
+    pub const NONE: FaceMask = FaceMask(0);
+
+    pub fn contains(self, face: BoxFace) -> bool {
+        self.0 & (1 << face as u8) != 0
+    }
+
+    pub fn with(self, face: BoxFace) -> Self {
+        FaceMask(self.0 | (1 << face as u8))
+    }
+
+    pub fn without(self, face: BoxFace) -> Self {
+        FaceMask(self.0 & !(1 << face as u8))
+    }
+}
+
+/// Builds axis-aligned boxes on the voxel grid, from the origin to `size`.
+#[derive(Clone, Debug)]
+pub struct BoxBuilder {
+    size: [i8; 3],
+    face_colors: [[f32; 3]; 6],
+    face_mask: FaceMask,
+}
+
+impl BoxBuilder {
+    pub fn new(size: [i8; 3]) -> Self {
+        Self {
+            size,
+            face_colors: [[1.0, 1.0, 1.0]; 6],
+            face_mask: FaceMask::ALL,
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.face_colors = [color; 6];
+        self
+    }
+
+    pub fn with_face_color(mut self, face: BoxFace, color: [f32; 3]) -> Self {
+        self.face_colors[face as usize] = color;
+        self
+    }
+
+    pub fn with_face_mask(mut self, face_mask: FaceMask) -> Self {
+        self.face_mask = face_mask;
+        self
+    }
+
+    pub fn build_vertices(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for (face, face_corners) in BOX_FACES.iter().zip(FACES.iter()) {
+            if !self.face_mask.contains(*face) {
+                continue;
+            }
+
+            let color = self.face_colors[*face as usize];
+
+            push_quad_indices(&mut indices, vertices.len() as u32);
+
+            for corner in &face_corners.corners {
+                let position = [
+                    f32::from(corner[0] * self.size[0]),
+                    f32::from(corner[1] * self.size[1]),
+                    f32::from(corner[2] * self.size[2]),
+                ];
+
+                vertices.push(Vertex::new(position, color));
+            }
+        }
+
+        (vertices, indices)
+    }
+
+    /// Uploads the box with its minimum corner at `position`.
+    pub fn build(&self, renderer: &mut Renderer, position: Vector3<f32>) -> VoxelMesh {
+        let (vertices, indices) = self.build_vertices();
+
+        let up = Vector3::unit_y();
+        let forward = Vector3::unit_z();
+        let model = Transform::new(position, up, forward).get_transform();
+
+        VoxelMesh::new(renderer, &vertices, &indices, model)
+    }
+}
+
+/// Unit cube filling the grid cell whose minimum corner is `position`.
+pub fn cube_mesh_builder(
+    renderer: &mut Renderer,
+    position: Vector3<f32>,
+    color: [f32; 3],
+) -> VoxelMesh {
+    BoxBuilder::new([1, 1, 1])
+        .with_color(color)
+        .build(renderer, position)
+}
+
+#[test]
+fn test_box_vertices() {
+    let (vertices, indices) = BoxBuilder::new([2, 1, 3])
+        .with_face_mask(FaceMask::ALL.without(BoxFace::Bottom))
+        .with_face_color(BoxFace::Top, [0.0, 1.0, 0.0])
+        .build_vertices();
+
+    assert_eq!(vertices.len(), 5 * 4);
+    assert_eq!(indices.len(), 5 * 6);
+
+    for vertex in &vertices {
+        assert!(vertex.pos[0] == 0.0 || vertex.pos[0] == 2.0);
+        assert!(vertex.pos[1] == 0.0 || vertex.pos[1] == 1.0);
+        assert!(vertex.pos[2] == 0.0 || vertex.pos[2] == 3.0);
+    }
+
+    let top_vertices = vertices
+        .iter()
+        .filter(|vertex| vertex.color == [0.0, 1.0, 0.0]);
+    assert!(top_vertices.clone().all(|vertex| vertex.pos[1] == 1.0));
+    assert_eq!(top_vertices.count(), 4);
 }
@@ -0,0 +1,208 @@
+use super::gfx;
+use super::line_mesh::{pipe, Locals, LINE_DEPTH_BIAS};
+use super::{box_wireframe_vertices, LineMeshPipe, LineVertex, Mesh, Renderer, Resources};
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector3};
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+
+// Segments of each of the three circles making up a sphere.
+const SPHERE_SEGMENTS: usize = 24;
+const INITIAL_CAPACITY: usize = 4096;
+
+/// Three great circles around `center`, one per axis plane.
+pub fn sphere_wireframe_vertices(
+    center: Point3<f32>,
+    radius: f32,
+    color: [f32; 3],
+) -> Vec<LineVertex> {
+    let mut vertices = Vec::with_capacity(3 * SPHERE_SEGMENTS * 2);
+    let point = |axis: usize, angle: f32| {
+        let mut offset = Vector3::zero();
+        offset[(axis + 1) % 3] = angle.cos() * radius;
+        offset[(axis + 2) % 3] = angle.sin() * radius;
+        (center + offset).into()
+    };
+
+    for axis in 0..3 {
+        for segment in 0..SPHERE_SEGMENTS {
+            let angle = segment as f32 / SPHERE_SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+            let next_angle =
+                (segment + 1) as f32 / SPHERE_SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+
+            vertices.push(LineVertex::new(point(axis, angle), color));
+            vertices.push(LineVertex::new(point(axis, next_angle), color));
+        }
+    }
+
+    vertices
+}
+
+/// Lines queued during a frame from anywhere in game code, uploaded into a
+/// single dynamic vertex buffer and drawn at once by `render`.
+pub struct DebugDraw {
+    vertices: Vec<LineVertex>,
+    enabled: bool,
+    slice: gfx::Slice<Resources>,
+    data: pipe::Data<Resources>,
+    capacity: usize,
+}
+
+impl DebugDraw {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let data = pipe::Data {
+            vbuf: Self::create_vertex_buffer(renderer, INITIAL_CAPACITY),
+            locals: renderer.factory.create_constant_buffer(1),
+            out: renderer.scene.color.clone(),
+            out_depth: renderer.scene.depth_stencil.clone(),
+        };
+
+        Self {
+            vertices: Vec::new(),
+            enabled: false,
+            slice: gfx::Slice {
+                start: 0,
+                end: 0,
+                base_vertex: 0,
+                instances: None,
+                buffer: gfx::IndexBuffer::Auto,
+            },
+            data,
+            capacity: INITIAL_CAPACITY,
+        }
+    }
+
+    fn create_vertex_buffer(
+        renderer: &mut Renderer,
+        capacity: usize,
+    ) -> gfx::handle::Buffer<Resources, LineVertex> {
+        renderer
+            .factory
+            .create_buffer(
+                capacity,
+                gfx::buffer::Role::Vertex,
+                gfx::memory::Usage::Dynamic,
+                gfx::memory::Bind::empty(),
+            )
+            .unwrap()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn line(&mut self, from: Point3<f32>, to: Point3<f32>, color: [f32; 3]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.vertices.push(LineVertex::new(from.into(), color));
+        self.vertices.push(LineVertex::new(to.into(), color));
+    }
+
+    pub fn aabb(&mut self, min: Point3<f32>, max: Point3<f32>, color: [f32; 3]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.vertices
+            .extend(box_wireframe_vertices(min.into(), max.into(), color));
+    }
+
+    pub fn sphere(&mut self, center: Point3<f32>, radius: f32, color: [f32; 3]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.vertices
+            .extend(sphere_wireframe_vertices(center, radius, color));
+    }
+
+    pub fn ray(
+        &mut self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        length: f32,
+        color: [f32; 3],
+    ) {
+        self.line(origin, origin + direction.normalize() * length, color);
+    }
+
+    /// Red, green and blue lines along the x, y and z axes.
+    pub fn axes(&mut self, origin: Point3<f32>, size: f32) {
+        self.line(origin, origin + Vector3::unit_x() * size, [1.0, 0.0, 0.0]);
+        self.line(origin, origin + Vector3::unit_y() * size, [0.0, 1.0, 0.0]);
+        self.line(origin, origin + Vector3::unit_z() * size, [0.0, 0.0, 1.0]);
+    }
+
+    /// Draws and clears the lines queued this frame.
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        pipe: &LineMeshPipe,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+    ) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if self.vertices.len() > self.capacity {
+            self.capacity = self.vertices.len().next_power_of_two();
+            self.data.vbuf = Self::create_vertex_buffer(renderer, self.capacity);
+        }
+
+        let locals = Locals {
+            model: Matrix4::identity().into(),
+            view: (*view).into(),
+            proj: (*proj).into(),
+            params: [LINE_DEPTH_BIAS, 0.0, 0.0, 0.0],
+        };
+
+        renderer
+            .encoder
+            .update_buffer(&self.data.vbuf, &self.vertices, 0)
+            .unwrap();
+        renderer
+            .encoder
+            .update_buffer(&self.data.locals, &[locals], 0)
+            .unwrap();
+        self.slice.end = self.vertices.len() as u32;
+        self.data.out = renderer.scene.color.clone();
+        self.data.out_depth = renderer.scene.depth_stencil.clone();
+        renderer.draw(self, pipe);
+
+        self.vertices.clear();
+    }
+}
+
+impl Mesh<pipe::Data<Resources>> for DebugDraw {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+#[test]
+fn test_sphere_wireframe_vertices() {
+    let center = Point3::new(1.0, 2.0, 3.0);
+    let vertices = sphere_wireframe_vertices(center, 2.0, [1.0; 3]);
+
+    assert_eq!(vertices.len(), 3 * SPHERE_SEGMENTS * 2);
+
+    for vertex in &vertices {
+        let distance = Point3::from(vertex.pos).distance(center);
+        assert!((distance - 2.0).abs() < 1e-5);
+    }
+}
@@ -40,6 +40,12 @@ impl Events {
                     } => *running = false,
                     WindowEvent::Resized(size) => {
                         renderer.resize(size);
+                        input_obj.set_resized((size.width, size.height));
+                    }
+                    WindowEvent::HiDpiFactorChanged(_) => {
+                        let size = renderer.get_size();
+                        renderer.resize(size);
+                        input_obj.set_resized((size.width, size.height));
                     }
                     WindowEvent::KeyboardInput { input, .. } => match input {
                         KeyboardInput {
@@ -60,7 +66,7 @@ impl Events {
                         }
                     },
                     WindowEvent::CursorMoved { position, .. } => {
-                        let logical_size = renderer.window.get_inner_size().unwrap();
+                        let logical_size = renderer.get_size();
                         let window_center_x = logical_size.width / 2.0;
                         let window_center_y = logical_size.height / 2.0;
 
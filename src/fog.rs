@@ -0,0 +1,98 @@
+use super::FrameLocals;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    Disabled,
+    // Fades from `start` to fully fogged at `end`.
+    Linear,
+    // Fades with `1 - e^(-density * distance)` past `start`.
+    Exponential,
+    // Fades with `1 - e^(-(density * distance)^2)` past `start`.
+    ExponentialSquared,
+}
+
+impl FogMode {
+    fn to_shader_value(self) -> f32 {
+        match self {
+            FogMode::Disabled => 0.0,
+            FogMode::Linear => 1.0,
+            FogMode::Exponential => 2.0,
+            FogMode::ExponentialSquared => 3.0,
+        }
+    }
+}
+
+/// Distance fog applied by the voxel shader, meant to share the sky color so
+/// chunks fade into the background at the edge of the view distance.
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: [f32; 3],
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Linear,
+            color: [0.6, 0.75, 0.9],
+            start: 50.0,
+            end: 100.0,
+            density: 0.02,
+        }
+    }
+}
+
+impl Fog {
+    pub fn update_frame_locals(&self, frame_locals: &mut FrameLocals) {
+        frame_locals.fog_color = [self.color[0], self.color[1], self.color[2], 1.0];
+        frame_locals.fog_params = [
+            self.start,
+            self.end,
+            self.density,
+            self.mode.to_shader_value(),
+        ];
+    }
+}
+
+#[test]
+fn test_fog_frame_locals() {
+    let fog = Fog {
+        mode: FogMode::Exponential,
+        color: [0.1, 0.2, 0.3],
+        start: 10.0,
+        end: 80.0,
+        density: 0.05,
+    };
+    let mut frame_locals = FrameLocals::default();
+
+    fog.update_frame_locals(&mut frame_locals);
+
+    assert_eq!(frame_locals.fog_color, [0.1, 0.2, 0.3, 1.0]);
+    assert_eq!(frame_locals.fog_params, [10.0, 80.0, 0.05, 2.0]);
+}
+
+#[test]
+fn test_fog_mode_shader_values() {
+    assert_eq!(FogMode::Disabled.to_shader_value(), 0.0);
+    assert_eq!(FogMode::Linear.to_shader_value(), 1.0);
+    assert_eq!(FogMode::Exponential.to_shader_value(), 2.0);
+    assert_eq!(FogMode::ExponentialSquared.to_shader_value(), 3.0);
+
+    // The voxel shaders branch on the same values, anything else is unfogged.
+    for shader in &[
+        include_str!("shader/triangle_150_core.glslf"),
+        include_str!("shader/triangle_300_es.glslf"),
+    ] {
+        for mode in &[
+            FogMode::Linear,
+            FogMode::Exponential,
+            FogMode::ExponentialSquared,
+        ] {
+            let branch = format!("mode == {}", mode.to_shader_value());
+            assert!(shader.contains(&branch), "{}", branch);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::gfx;
+
+gfx_defines! {
+    // Uniforms shared by every world pipeline, uploaded once per frame with
+    // `Renderer::update_frame_locals`.
+    constant FrameLocals {
+        sun_direction: [f32; 4] = "u_SunDirection",
+        sun_color: [f32; 4] = "u_SunColor",
+        ambient_color: [f32; 4] = "u_AmbientColor",
+        fog_color: [f32; 4] = "u_FogColor",
+        // Start, end, density and mode, see `Fog`.
+        fog_params: [f32; 4] = "u_FogParams",
+        shadow_matrix_0: [[f32; 4]; 4] = "u_ShadowMatrix0",
+        shadow_matrix_1: [[f32; 4]; 4] = "u_ShadowMatrix1",
+        shadow_matrix_2: [[f32; 4]; 4] = "u_ShadowMatrix2",
+        // Far distance of each cascade, w is 0 when shadows are disabled.
+        shadow_splits: [f32; 4] = "u_ShadowSplits",
+    }
+}
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+impl Default for FrameLocals {
+    fn default() -> Self {
+        Self {
+            sun_direction: [0.0, 1.0, 0.0, 0.0],
+            sun_color: [1.0, 1.0, 1.0, 1.0],
+            ambient_color: [0.3, 0.3, 0.3, 1.0],
+            fog_color: [0.0, 0.0, 0.0, 1.0],
+            fog_params: [0.0, 0.0, 0.0, 0.0],
+            shadow_matrix_0: IDENTITY,
+            shadow_matrix_1: IDENTITY,
+            shadow_matrix_2: IDENTITY,
+            shadow_splits: [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use super::{ImageId, Rect};
+
+/// Filled rectangle in window pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quad {
+    pub rect: Rect,
+    pub color: [f32; 4],
+    /// Region of the image, or of the font atlas without one, tinted by
+    /// `color`, in texture coordinates.
+    pub uv: Option<Rect>,
+    pub image: Option<ImageId>,
+    /// Drawn inside the rect, over `color`.
+    pub border_width: f32,
+    pub border_color: [f32; 4],
+    pub corner_radius: f32,
+}
+
+/// Everything the GUI draws in a frame, back to front, independent of the renderer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawList {
+    pub quads: Vec<Quad>,
+}
+
+impl DrawList {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push_quad(&mut self, rect: Rect, color: [f32; 4]) {
+        self.push_rounded_quad(rect, color, 0.0, 0.0, [0.0; 4]);
+    }
+
+    pub fn push_rounded_quad(
+        &mut self,
+        rect: Rect,
+        color: [f32; 4],
+        corner_radius: f32,
+        border_width: f32,
+        border_color: [f32; 4],
+    ) {
+        self.quads.push(Quad {
+            rect,
+            color,
+            uv: None,
+            image: None,
+            border_width,
+            border_color,
+            corner_radius,
+        });
+    }
+
+    pub fn push_glyph_quad(&mut self, rect: Rect, color: [f32; 4], uv: Rect) {
+        self.quads.push(Quad {
+            rect,
+            color,
+            uv: Some(uv),
+            image: None,
+            border_width: 0.0,
+            border_color: [0.0; 4],
+            corner_radius: 0.0,
+        });
+    }
+
+    pub fn push_image_quad(&mut self, rect: Rect, color: [f32; 4], image: ImageId, uv: Rect) {
+        self.quads.push(Quad {
+            rect,
+            color,
+            uv: Some(uv),
+            image: Some(image),
+            border_width: 0.0,
+            border_color: [0.0; 4],
+            corner_radius: 0.0,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+}
+
+#[test]
+fn test_draw_list() {
+    use super::{square, Arg, Gui};
+
+    let gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .background_color(Arg::Value([1.0, 0.0, 0.0, 1.0]))
+        .child(
+            Gui::create_element()
+                .style(&mut square(50))
+                .background_color(Arg::Value([0.0, 1.0, 0.0, 1.0]))
+                .hover_background_color(Arg::Value([0.0, 0.0, 1.0, 1.0])),
+        )
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+
+    assert_eq!(
+        draw_list.quads,
+        vec![
+            Quad {
+                rect: Rect::new((0.0, 0.0), (100.0, 100.0)),
+                color: [1.0, 0.0, 0.0, 1.0],
+                uv: None,
+                image: None,
+                border_width: 0.0,
+                border_color: [0.0; 4],
+                corner_radius: 0.0,
+            },
+            Quad {
+                rect: Rect::new((0.0, 0.0), (50.0, 50.0)),
+                color: [0.0, 1.0, 0.0, 1.0],
+                uv: None,
+                image: None,
+                border_width: 0.0,
+                border_color: [0.0; 4],
+                corner_radius: 0.0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_border_and_corner_radius() {
+    use super::text::monospace_font;
+    use super::{square, Arg, Gui};
+
+    let mut gui = Gui::new();
+    gui.set_font(monospace_font());
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .background_color(Arg::Value([0.0, 0.0, 0.0, 0.5]))
+        .border_width(4.0)
+        .border_color(Arg::Value([1.0, 1.0, 1.0, 1.0]))
+        .corner_radius(8.0)
+        .child(gui.create_text("a").border_width(1.0))
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+
+    // Borders pad the children like yoga borders.
+    let text = &element.get_children()[0];
+    assert_eq!(text.get_rect().position, (4.0, 4.0));
+    assert_eq!(text.get_rect().size, (92.0, 22.0));
+
+    assert_eq!(draw_list.quads[0].color, [0.0, 0.0, 0.0, 0.5]);
+    assert_eq!(draw_list.quads[0].border_width, 4.0);
+    assert_eq!(draw_list.quads[0].corner_radius, 8.0);
+    // A text element with a border but no background only draws its border.
+    assert_eq!(draw_list.quads[1].color, [0.0; 4]);
+    assert_eq!(draw_list.quads[1].border_width, 1.0);
+    assert_eq!(draw_list.quads[1].border_color, [0.0, 0.0, 0.0, 1.0]);
+}
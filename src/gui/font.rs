@@ -0,0 +1,230 @@
+use super::Rect;
+use rusttype::{point, Font, Scale};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Printable ASCII, rasterised when the atlas is created.
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+const ATLAS_WIDTH: u32 = 512;
+// Empty pixels around each glyph so bilinear filtering doesn't bleed between them.
+const GLYPH_PADDING: u32 = 1;
+// Opaque block in the top left corner, sampled by untextured quads.
+const WHITE_SIZE: u32 = 2;
+
+/// Metrics of a character rasterised in a `FontAtlas`, in atlas pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyph {
+    pub advance: f32,
+    /// Top left of the bitmap from the pen position on the top of the line.
+    pub offset: (f32, f32),
+    pub size: (f32, f32),
+    /// Bitmap in the atlas, in texture coordinates.
+    pub uv: Rect,
+}
+
+/// Single channel coverage bitmap of the glyphs of a font at one size, scaled
+/// to any font size when drawn.
+#[derive(Clone, Debug)]
+pub struct FontAtlas {
+    font_size: f32,
+    line_height: f32,
+    glyphs: HashMap<char, Glyph>,
+    size: (u32, u32),
+    pixels: Vec<u8>,
+}
+
+impl FontAtlas {
+    /// Atlas without glyphs, all transparent but for the white block.
+    pub fn new(font_size: f32, line_height: f32, size: (u32, u32)) -> Self {
+        let mut pixels = vec![0; (size.0 * size.1) as usize];
+
+        for y in 0..WHITE_SIZE.min(size.1) {
+            for x in 0..WHITE_SIZE.min(size.0) {
+                pixels[(y * size.0 + x) as usize] = 255;
+            }
+        }
+
+        Self {
+            font_size,
+            line_height,
+            glyphs: HashMap::new(),
+            size,
+            pixels,
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P, font_size: f32) -> io::Result<Self> {
+        Self::from_ttf(fs::read(path)?, font_size)
+    }
+
+    /// Rasterises the printable ASCII characters of a TrueType font at `font_size` pixels.
+    pub fn from_ttf(bytes: Vec<u8>, font_size: f32) -> io::Result<Self> {
+        let font = Font::from_bytes(bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let scale = Scale::uniform(font_size);
+        let v_metrics = font.v_metrics(scale);
+        let glyphs: Vec<_> = (FIRST_CHAR..=LAST_CHAR)
+            .map(|c| {
+                let glyph = font.glyph(c as char).scaled(scale);
+                let advance = glyph.h_metrics().advance_width;
+
+                (
+                    c as char,
+                    advance,
+                    glyph.positioned(point(0.0, v_metrics.ascent)),
+                )
+            })
+            .collect();
+
+        let sizes: Vec<_> = glyphs
+            .iter()
+            .map(|(_, _, glyph)| match glyph.pixel_bounding_box() {
+                Some(bounds) => (bounds.width() as u32, bounds.height() as u32),
+                None => (0, 0),
+            })
+            .collect();
+        let ((width, height), positions) = pack_glyphs(&sizes);
+        let mut atlas = Self::new(
+            font_size,
+            v_metrics.ascent - v_metrics.descent + v_metrics.line_gap,
+            (width, height),
+        );
+
+        for ((c, advance, glyph), (x, y)) in glyphs.into_iter().zip(positions) {
+            let (offset, size) = match glyph.pixel_bounding_box() {
+                Some(bounds) => {
+                    glyph.draw(|glyph_x, glyph_y, coverage| {
+                        let index = (y + glyph_y) * width + x + glyph_x;
+                        atlas.pixels[index as usize] = (coverage * 255.0).round() as u8;
+                    });
+
+                    (
+                        (bounds.min.x as f32, bounds.min.y as f32),
+                        (bounds.width() as f32, bounds.height() as f32),
+                    )
+                }
+                None => ((0.0, 0.0), (0.0, 0.0)),
+            };
+
+            let uv = Rect::new(
+                (x as f32 / width as f32, y as f32 / height as f32),
+                (size.0 / width as f32, size.1 / height as f32),
+            );
+
+            atlas.insert_glyph(
+                c,
+                Glyph {
+                    advance,
+                    offset,
+                    size,
+                    uv,
+                },
+            );
+        }
+
+        Ok(atlas)
+    }
+
+    pub fn insert_glyph(&mut self, c: char, glyph: Glyph) {
+        self.glyphs.insert(c, glyph);
+    }
+
+    pub fn get_glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c)
+    }
+
+    /// Size in pixels the glyphs were rasterised at.
+    pub fn get_font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    pub fn get_line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Texture coordinates of a fully opaque texel.
+    pub fn get_white_uv(&self) -> [f32; 2] {
+        [
+            (WHITE_SIZE / 2) as f32 / self.size.0 as f32,
+            (WHITE_SIZE / 2) as f32 / self.size.1 as f32,
+        ]
+    }
+}
+
+/// Shelf packing: rows filled from left to right, as high as their highest glyph.
+/// Returns the size of the atlas and the top left of each glyph in it, the
+/// atlas being wider than `ATLAS_WIDTH` when a glyph doesn't fit in it.
+fn pack_glyphs(sizes: &[(u32, u32)]) -> ((u32, u32), Vec<(u32, u32)>) {
+    let widest = sizes.iter().map(|&(width, _)| width).max().unwrap_or(0);
+    let atlas_width = ATLAS_WIDTH.max((widest + 2 * GLYPH_PADDING).next_power_of_two());
+    let mut positions = Vec::with_capacity(sizes.len());
+    let mut cursor = (WHITE_SIZE + GLYPH_PADDING, GLYPH_PADDING);
+    let mut row_height = WHITE_SIZE;
+
+    for &(width, height) in sizes {
+        if cursor.0 + width + GLYPH_PADDING > atlas_width {
+            cursor = (GLYPH_PADDING, cursor.1 + row_height + GLYPH_PADDING);
+            row_height = 0;
+        }
+
+        positions.push(cursor);
+        cursor.0 += width + GLYPH_PADDING;
+        row_height = row_height.max(height);
+    }
+
+    let atlas_height = (cursor.1 + row_height + GLYPH_PADDING).next_power_of_two();
+
+    ((atlas_width, atlas_height), positions)
+}
+
+impl Default for FontAtlas {
+    fn default() -> Self {
+        Self::new(16.0, 16.0, (WHITE_SIZE, WHITE_SIZE))
+    }
+}
+
+#[test]
+fn test_white_uv() {
+    let atlas = FontAtlas::new(16.0, 20.0, (8, 4));
+    let [u, v] = atlas.get_white_uv();
+    let (x, y) = ((u * 8.0) as usize, (v * 4.0) as usize);
+
+    // The four texels around the white texture coordinates are opaque.
+    for &(x, y) in &[(x - 1, y - 1), (x, y - 1), (x - 1, y), (x, y)] {
+        assert_eq!(atlas.get_pixels()[y * 8 + x], 255);
+    }
+    assert_eq!(atlas.get_pixels()[2], 0);
+}
+
+#[test]
+fn test_pack_glyphs() {
+    let ((width, height), positions) = pack_glyphs(&[(10, 20), (500, 30), (8, 8)]);
+
+    assert_eq!(width, ATLAS_WIDTH);
+    assert_eq!(positions, vec![(3, 1), (1, 22), (502, 22)]);
+    assert_eq!(height, 64);
+}
+
+#[test]
+fn test_pack_glyphs_wider_than_the_atlas() {
+    // The wide glyph takes a whole row.
+    let sizes = [(10, 20), (1022, 10), (10, 20)];
+    let ((width, height), positions) = pack_glyphs(&sizes);
+
+    assert_eq!(width, 1024);
+    for (&(x, y), &(glyph_width, glyph_height)) in positions.iter().zip(&sizes) {
+        assert!(x + glyph_width + GLYPH_PADDING <= width);
+        assert!(y + glyph_height + GLYPH_PADDING <= height);
+    }
+}
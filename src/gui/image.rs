@@ -0,0 +1,261 @@
+use super::Rect;
+use png::HasParameters;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use yoga::{MeasureMode, Node, NodeRef, Size};
+
+/// Handle of an image added to the `Gui`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ImageId(pub(crate) usize);
+
+/// RGBA pixels, row by row starting from the top.
+#[derive(Clone, Debug)]
+pub struct Image {
+    size: (u32, u32),
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(size: (u32, u32), pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), (size.0 * size.1 * 4) as usize);
+
+        Self { size, pixels }
+    }
+
+    pub fn from_png<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        // NOTE: Converts palettes and other bit depths to 8 bits per channel.
+        decoder.set(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder
+            .read_info()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let mut buffer = vec![0; info.buffer_size()];
+        reader
+            .next_frame(&mut buffer)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+        let pixels = match info.color_type {
+            png::ColorType::RGBA => buffer,
+            png::ColorType::RGB => buffer
+                .chunks(3)
+                .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks(2)
+                .flat_map(|la| vec![la[0], la[0], la[0], la[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&l| vec![l, l, l, 255]).collect(),
+            png::ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Unexpanded indexed colors",
+                ))
+            }
+        };
+
+        Ok(Self::new((info.width, info.height), pixels))
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// Insets in image pixels splitting an image in 3×3 regions: corners keep their
+/// size, edges stretch along the border and the center stretches both ways.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NineSlice {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl NineSlice {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn uniform(inset: f32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+}
+
+/// Image drawn by an element, also the context of its yoga node for measuring.
+#[derive(Clone, Debug)]
+pub struct ImageContent {
+    pub id: ImageId,
+    pub size: (u32, u32),
+    /// Multiplied with the image colors.
+    pub color: [f32; 4],
+    pub nine_slice: Option<NineSlice>,
+}
+
+impl ImageContent {
+    pub fn new(id: ImageId, size: (u32, u32)) -> Self {
+        Self {
+            id,
+            size,
+            color: [1.0, 1.0, 1.0, 1.0],
+            nine_slice: None,
+        }
+    }
+
+    /// Quads and their texture coordinates for the image stretched over `rect`.
+    pub fn layout_quads(&self, rect: &Rect) -> Vec<(Rect, Rect)> {
+        let nine_slice = match self.nine_slice {
+            Some(nine_slice) => nine_slice,
+            None => return vec![(rect.clone(), Rect::new((0.0, 0.0), (1.0, 1.0)))],
+        };
+
+        let image_width = self.size.0 as f32;
+        let image_height = self.size.1 as f32;
+        // Bounds of the three columns and rows, on screen and in the image.
+        let xs = [
+            rect.position.0,
+            rect.position.0 + nine_slice.left,
+            rect.position.0 + rect.size.0 - nine_slice.right,
+            rect.position.0 + rect.size.0,
+        ];
+        let ys = [
+            rect.position.1,
+            rect.position.1 + nine_slice.top,
+            rect.position.1 + rect.size.1 - nine_slice.bottom,
+            rect.position.1 + rect.size.1,
+        ];
+        let us = [
+            0.0,
+            nine_slice.left / image_width,
+            1.0 - nine_slice.right / image_width,
+            1.0,
+        ];
+        let vs = [
+            0.0,
+            nine_slice.top / image_height,
+            1.0 - nine_slice.bottom / image_height,
+            1.0,
+        ];
+
+        let mut quads = Vec::with_capacity(9);
+        for row in 0..3 {
+            for column in 0..3 {
+                let rect = Rect::new(
+                    (xs[column], ys[row]),
+                    (xs[column + 1] - xs[column], ys[row + 1] - ys[row]),
+                );
+
+                if rect.size.0 > 0.0 && rect.size.1 > 0.0 {
+                    let uv = Rect::new(
+                        (us[column], vs[row]),
+                        (us[column + 1] - us[column], vs[row + 1] - vs[row]),
+                    );
+                    quads.push((rect, uv));
+                }
+            }
+        }
+
+        quads
+    }
+}
+
+/// Yoga measure function of image nodes, sized like their image by default.
+pub extern "C" fn measure_image(
+    node_ref: NodeRef,
+    _width: f32,
+    _width_mode: MeasureMode,
+    _height: f32,
+    _height_mode: MeasureMode,
+) -> Size {
+    let image =
+        Node::get_context(&node_ref).and_then(|context| context.downcast_ref::<ImageContent>());
+    let (width, height) = image.map_or((0, 0), |image| image.size);
+
+    Size {
+        width: width as f32,
+        height: height as f32,
+    }
+}
+
+#[test]
+fn test_nine_slice_quads() {
+    let mut image = ImageContent::new(ImageId(0), (16, 16));
+    image.nine_slice = Some(NineSlice::uniform(4.0));
+
+    let quads = image.layout_quads(&Rect::new((10.0, 20.0), (100.0, 40.0)));
+
+    assert_eq!(quads.len(), 9);
+    // Corners keep their size, the center stretches.
+    assert_eq!(
+        quads[0],
+        (
+            Rect::new((10.0, 20.0), (4.0, 4.0)),
+            Rect::new((0.0, 0.0), (0.25, 0.25))
+        )
+    );
+    assert_eq!(
+        quads[4],
+        (
+            Rect::new((14.0, 24.0), (92.0, 32.0)),
+            Rect::new((0.25, 0.25), (0.5, 0.5))
+        )
+    );
+    assert_eq!(
+        quads[8],
+        (
+            Rect::new((106.0, 56.0), (4.0, 4.0)),
+            Rect::new((0.75, 0.75), (0.25, 0.25))
+        )
+    );
+}
+
+#[test]
+fn test_image_elements() {
+    use super::{square, Arg, DrawList, Gui};
+    use yoga::{Align, FlexStyle};
+
+    let mut gui = Gui::new();
+    gui.resize(100.0, 100.0, 1.0);
+    let icon = gui.add_image(Image::new((16, 8), vec![255; 16 * 8 * 4]));
+    let mut element = Gui::create_element()
+        .style(&mut vec![FlexStyle::AlignItems(Align::FlexStart)])
+        .child(gui.create_image(icon))
+        .child(
+            gui.create_image(icon)
+                .nine_slice(NineSlice::uniform(2.0))
+                .color(Arg::Value([1.0, 0.0, 0.0, 1.0]))
+                .style(&mut square(40)),
+        )
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+
+    let children = element.get_children();
+    assert_eq!(children[0].get_rect().size, (16.0, 8.0));
+    assert_eq!(children[1].get_rect().size, (40.0, 40.0));
+    // The white root, the whole first image then the nine slices of the second.
+    assert_eq!(draw_list.quads.len(), 1 + 1 + 9);
+    assert_eq!(draw_list.quads[1].image, Some(icon));
+    assert_eq!(draw_list.quads[10].color, [1.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+#[should_panic(expected = "nine_slice is only for image elements")]
+fn test_image_modifiers_need_an_image() {
+    use super::Gui;
+
+    Gui::create_element()
+        .nine_slice(NineSlice::uniform(2.0))
+        .build();
+}
@@ -1,18 +1,31 @@
 use super::super::gfx;
 use super::super::gfx::traits::FactoryExt;
 use super::super::{ColorFormat, Mesh, Pipeline, Renderer, Resources};
-use super::Rect;
-use glutin::dpi::LogicalSize;
+use super::{DrawList, FontAtlas, Image, ImageId, Quad, Rect};
+use gfx::Factory;
+use std::ops::Range;
+use std::rc::Rc;
+
+const INITIAL_CAPACITY: usize = 1024;
 
 gfx_defines! {
     vertex Vertex {
         pos: [f32; 2] = "a_Pos",
-        color: [f32; 3] = "a_Color",
+        color: [f32; 4] = "a_Color",
+        uv: [f32; 2] = "a_Uv",
+        border_color: [f32; 4] = "a_BorderColor",
+        // From the center of the quad and half its size, in physical pixels.
+        local: [f32; 2] = "a_Local",
+        half_size: [f32; 2] = "a_HalfSize",
+        // Corner radius and border width, in physical pixels.
+        shape: [f32; 2] = "a_Shape",
     }
 
     pipeline pipe {
         vbuf: gfx::VertexBuffer<Vertex> = (),
-        out: gfx::RenderTarget<ColorFormat> = "Target0",
+        texture: gfx::TextureSampler<[f32; 4]> = "t_Texture",
+        out: gfx::BlendTarget<ColorFormat> =
+            ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
     }
 }
 
@@ -40,62 +53,229 @@ impl Pipeline<pipe::Data<Resources>> for UIMeshPipe {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct UIMesh {
-    pub slice: gfx::Slice<Resources>,
-    pub data: pipe::Data<Resources>,
+#[inline]
+fn pixel_to_homogeneous_coordinate(x: f32, y: f32, framebuffer_size: (u32, u32)) -> [f32; 2] {
+    [
+        x * 2.0 / framebuffer_size.0 as f32 - 1.0,
+        1.0 - y * 2.0 / framebuffer_size.1 as f32,
+    ]
 }
 
-#[inline]
-fn pixel_to_homogeneous_coordinate(x: f32, y: f32, screen_size: LogicalSize) -> (f32, f32) {
-    (
-        x * 2.0 / screen_size.width as f32 - 1.0,
-        2.0 - y * 2.0 / screen_size.height as f32 - 1.0,
-    )
+fn push_quad_vertices(
+    vertices: &mut Vec<Vertex>,
+    quad: &Quad,
+    uv: &Rect,
+    framebuffer_size: (u32, u32),
+    hidpi_factor: f32,
+) {
+    // NOTE: Logical pixels are snapped to physical ones to keep edges sharp on HiDPI screens.
+    let left = (quad.rect.position.0 * hidpi_factor).round();
+    let top = (quad.rect.position.1 * hidpi_factor).round();
+    let right = ((quad.rect.position.0 + quad.rect.size.0) * hidpi_factor).round();
+    let bottom = ((quad.rect.position.1 + quad.rect.size.1) * hidpi_factor).round();
+    let half_size = [(right - left) / 2.0, (bottom - top) / 2.0];
+    let shape = [
+        (quad.corner_radius * hidpi_factor)
+            .min(half_size[0])
+            .min(half_size[1]),
+        quad.border_width * hidpi_factor,
+    ];
+
+    let (uv_left, uv_top) = uv.position;
+    let (uv_right, uv_bottom) = (uv.position.0 + uv.size.0, uv.position.1 + uv.size.1);
+    let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex {
+        pos: pixel_to_homogeneous_coordinate(x, y, framebuffer_size),
+        color: quad.color,
+        uv: [u, v],
+        border_color: quad.border_color,
+        local: [x - left - half_size[0], y - top - half_size[1]],
+        half_size,
+        shape,
+    };
+
+    let top_left = vertex(left, top, uv_left, uv_top);
+    let top_right = vertex(right, top, uv_right, uv_top);
+    let bottom_right = vertex(right, bottom, uv_right, uv_bottom);
+    let bottom_left = vertex(left, bottom, uv_left, uv_bottom);
+
+    vertices.extend_from_slice(&[
+        top_left,
+        top_right,
+        bottom_right,
+        bottom_right,
+        bottom_left,
+        top_left,
+    ]);
 }
 
-impl UIMesh {
-    pub fn new(renderer: &mut Renderer, rect: &Rect, color: [f32; 3]) -> Self {
-        let screen_size = renderer.window.get_outer_size().unwrap();
-        let position_left_top =
-            pixel_to_homogeneous_coordinate(rect.position.0, rect.position.1, screen_size);
-        let position_right_bottom = pixel_to_homogeneous_coordinate(
-            rect.position.0 + rect.size.0,
-            rect.position.1 + rect.size.1,
-            screen_size,
+/// Two triangles per quad of `draw_list`, laid out in logical pixels, for a
+/// framebuffer of `framebuffer_size` physical pixels. Untextured quads sample
+/// the font atlas at `white_uv`.
+fn build_ui_vertices(
+    draw_list: &DrawList,
+    framebuffer_size: (u32, u32),
+    hidpi_factor: f32,
+    white_uv: [f32; 2],
+) -> Vec<Vertex> {
+    let white = Rect::new((white_uv[0], white_uv[1]), (0.0, 0.0));
+    let mut vertices = Vec::with_capacity(draw_list.quads.len() * 6);
+
+    for quad in &draw_list.quads {
+        push_quad_vertices(
+            &mut vertices,
+            quad,
+            quad.uv.as_ref().unwrap_or(&white),
+            framebuffer_size,
+            hidpi_factor,
         );
+    }
 
-        let top_left = Vertex {
-            pos: [position_left_top.0, position_left_top.1],
-            color,
-        };
-        let top_right = Vertex {
-            pos: [position_right_bottom.0, position_left_top.1],
-            color,
-        };
-        let bottom_right = Vertex {
-            pos: [position_right_bottom.0, position_right_bottom.1],
-            color,
-        };
-        let bottom_left = Vertex {
-            pos: [position_left_top.0, position_right_bottom.1],
-            color,
-        };
+    vertices
+}
 
-        let suqare: &[Vertex] = &[top_left, top_right, bottom_right, bottom_left];
+/// Runs of consecutive quads drawn with the same texture, as vertex ranges.
+fn texture_batches(draw_list: &DrawList) -> Vec<(Option<ImageId>, Range<u32>)> {
+    let mut batches: Vec<(Option<ImageId>, Range<u32>)> = Vec::new();
 
-        const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
+    for (i, quad) in draw_list.quads.iter().enumerate() {
+        let end = (i as u32 + 1) * 6;
 
-        let (vbuf, slice) = renderer
-            .factory
-            .create_vertex_buffer_with_slice(suqare, INDICES);
+        if let Some((image, range)) = batches.last_mut() {
+            if *image == quad.image {
+                range.end = end;
+                continue;
+            }
+        }
+
+        batches.push((quad.image, end - 6..end));
+    }
+
+    batches
+}
+
+type TextureView = gfx::handle::ShaderResourceView<Resources, [f32; 4]>;
+
+fn create_texture(renderer: &mut Renderer, size: (u32, u32), pixels: &[u8]) -> TextureView {
+    let kind = gfx::texture::Kind::D2(
+        size.0 as gfx::texture::Size,
+        size.1 as gfx::texture::Size,
+        gfx::texture::AaMode::Single,
+    );
+    let (_, resource) = renderer
+        .factory
+        .create_texture_immutable_u8::<ColorFormat>(kind, gfx::texture::Mipmap::Provided, &[pixels])
+        .unwrap();
+
+    resource
+}
+
+/// Every quad of a GUI frame in a single dynamic vertex buffer, reused across
+/// frames and drawn with one draw call per texture change.
+#[derive(Clone, Debug)]
+pub struct UIMesh {
+    pub slice: gfx::Slice<Resources>,
+    pub data: pipe::Data<Resources>,
+    capacity: usize,
+    white_uv: [f32; 2],
+    font_texture: TextureView,
+    // Textures of the images of the `Gui`, in the same order.
+    image_textures: Vec<TextureView>,
+}
+
+impl UIMesh {
+    pub fn new(renderer: &mut Renderer, font: &FontAtlas) -> Self {
+        // NOTE: Glyph coverage goes in the alpha channel so glyphs are tinted like images.
+        let font_pixels: Vec<u8> = font
+            .get_pixels()
+            .iter()
+            .flat_map(|&coverage| vec![255, 255, 255, coverage])
+            .collect();
+        let font_texture = create_texture(renderer, font.get_size(), &font_pixels);
+        let sampler = renderer.factory.create_sampler_linear();
 
         let data = pipe::Data {
-            vbuf,
+            vbuf: Self::create_vertex_buffer(renderer, INITIAL_CAPACITY),
+            texture: (font_texture.clone(), sampler),
             out: renderer.render_target.clone(),
         };
 
-        Self { data, slice }
+        Self {
+            slice: gfx::Slice {
+                start: 0,
+                end: 0,
+                base_vertex: 0,
+                instances: None,
+                buffer: gfx::IndexBuffer::Auto,
+            },
+            data,
+            capacity: INITIAL_CAPACITY,
+            white_uv: font.get_white_uv(),
+            font_texture,
+            image_textures: Vec::new(),
+        }
+    }
+
+    fn create_vertex_buffer(
+        renderer: &mut Renderer,
+        capacity: usize,
+    ) -> gfx::handle::Buffer<Resources, Vertex> {
+        renderer
+            .factory
+            .create_buffer(
+                capacity,
+                gfx::buffer::Role::Vertex,
+                gfx::memory::Usage::Dynamic,
+                gfx::memory::Bind::empty(),
+            )
+            .unwrap()
+    }
+
+    /// Uploads and draws `draw_list`, whose image IDs index `images`.
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        pipe: &UIMeshPipe,
+        draw_list: &DrawList,
+        images: &[Rc<Image>],
+        hidpi_factor: f32,
+    ) {
+        for image in &images[self.image_textures.len()..] {
+            let texture = create_texture(renderer, image.get_size(), image.get_pixels());
+            self.image_textures.push(texture);
+        }
+
+        if draw_list.is_empty() {
+            return;
+        }
+
+        let vertices = build_ui_vertices(
+            draw_list,
+            renderer.get_framebuffer_size(),
+            hidpi_factor,
+            self.white_uv,
+        );
+
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.data.vbuf = Self::create_vertex_buffer(renderer, self.capacity);
+        }
+
+        renderer
+            .encoder
+            .update_buffer(&self.data.vbuf, &vertices, 0)
+            .unwrap();
+        // NOTE: Resizing the window replaces the render target.
+        self.data.out = renderer.render_target.clone();
+
+        for (image, range) in texture_batches(draw_list) {
+            self.data.texture.0 = match image {
+                Some(ImageId(index)) => self.image_textures[index].clone(),
+                None => self.font_texture.clone(),
+            };
+            self.slice.start = range.start;
+            self.slice.end = range.end;
+            renderer.draw(self, pipe);
+        }
     }
 }
 
@@ -108,3 +288,113 @@ impl Mesh<pipe::Data<Resources>> for UIMesh {
         &self.slice
     }
 }
+
+#[test]
+fn test_build_ui_vertices() {
+    let mut draw_list = DrawList::new();
+    draw_list.push_quad(Rect::new((0.0, 0.0), (100.0, 50.0)), [1.0, 0.0, 0.0, 1.0]);
+    draw_list.push_glyph_quad(
+        Rect::new((50.0, 50.0), (50.0, 50.0)),
+        [0.0, 1.0, 0.0, 1.0],
+        Rect::new((0.5, 0.25), (0.25, 0.5)),
+    );
+
+    let vertices = build_ui_vertices(&draw_list, (100, 100), 1.0, [0.125, 0.25]);
+
+    assert_eq!(vertices.len(), 12);
+    assert_eq!(vertices[2].uv, [0.125, 0.25]);
+    assert_eq!(vertices[6].uv, [0.5, 0.25]);
+    assert_eq!(vertices[8].uv, [0.75, 0.75]);
+    assert_eq!(vertices[0].pos, [-1.0, 1.0]);
+    assert_eq!(vertices[2].pos, [1.0, 0.0]);
+    assert_eq!(vertices[6].pos, [0.0, 0.0]);
+    assert_eq!(vertices[8].pos, [1.0, -1.0]);
+    assert_eq!(vertices[11].color, [0.0, 1.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_build_ui_vertices_hidpi() {
+    let mut draw_list = DrawList::new();
+    draw_list.push_quad(Rect::new((8.2, 0.0), (15.6, 16.0)), [1.0; 4]);
+
+    // 32×32 logical pixels at twice the density, edges land on whole physical pixels.
+    let vertices = build_ui_vertices(&draw_list, (64, 64), 2.0, [0.0, 0.0]);
+
+    assert_eq!(vertices[0].pos, [-0.5, 1.0]);
+    assert_eq!(vertices[2].pos, [0.5, 0.0]);
+    assert_eq!(vertices[0].local, [-16.0, -16.0]);
+    assert_eq!(vertices[2].half_size, [16.0, 16.0]);
+}
+
+#[test]
+fn test_rounded_quad_shape() {
+    let mut draw_list = DrawList::new();
+    draw_list.push_rounded_quad(
+        Rect::new((0.0, 0.0), (10.0, 20.0)),
+        [0.0, 0.0, 0.0, 0.5],
+        8.0,
+        1.0,
+        [1.0; 4],
+    );
+
+    let vertices = build_ui_vertices(&draw_list, (64, 64), 2.0, [0.0, 0.0]);
+
+    // In physical pixels, the radius is clamped to half the smallest side.
+    assert_eq!(vertices[0].shape, [10.0, 2.0]);
+    assert_eq!(vertices[0].color, [0.0, 0.0, 0.0, 0.5]);
+    assert_eq!(vertices[0].border_color, [1.0; 4]);
+}
+
+#[test]
+fn test_texture_batches() {
+    let mut draw_list = DrawList::new();
+    let rect = Rect::new((0.0, 0.0), (1.0, 1.0));
+    draw_list.push_quad(rect.clone(), [1.0; 4]);
+    draw_list.push_glyph_quad(rect.clone(), [1.0; 4], rect.clone());
+    draw_list.push_image_quad(rect.clone(), [1.0; 4], ImageId(1), rect.clone());
+    draw_list.push_image_quad(rect.clone(), [1.0; 4], ImageId(1), rect.clone());
+    draw_list.push_quad(rect.clone(), [1.0; 4]);
+
+    assert_eq!(
+        texture_batches(&draw_list),
+        vec![(None, 0..12), (Some(ImageId(1)), 12..24), (None, 24..30)]
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_render_headless() {
+    use super::super::RendererConfig;
+    use super::{Arg, Gui};
+    use yoga::prelude::*;
+    use yoga::FlexStyle;
+
+    let mut renderer = Renderer::new_headless(&RendererConfig::new().with_size(8, 8));
+    let pipe = UIMeshPipe::new(&mut renderer);
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .background_color(Arg::Value([0.0; 4]))
+        .child(
+            Gui::create_element()
+                .style(&mut vec![FlexStyle::Height(4.point())])
+                .background_color(Arg::Value([1.0, 0.0, 0.0, 1.0])),
+        )
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.resize(8.0, 8.0, 1.0);
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+    renderer.set_clear_color([0.0, 0.0, 0.0, 1.0]);
+    renderer.clear();
+    gui.render(&mut renderer, &pipe, &draw_list);
+    // Both untextured quads are batched in a single draw call.
+    assert_eq!(renderer.get_draw_calls(), 1);
+    renderer.flush();
+
+    // The child covers the top half, the transparent root lets the clear color through.
+    let pixels = renderer.read_pixels();
+    let (top, bottom) = pixels.split_at(8 * 4 * 4);
+    assert!(top.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    assert!(bottom.chunks(4).all(|pixel| pixel == [0, 0, 0, 255]));
+}
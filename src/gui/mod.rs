@@ -1,10 +1,77 @@
+mod draw_list;
+pub use self::draw_list::{DrawList, Quad};
+mod font;
+pub use self::font::{FontAtlas, Glyph};
+mod image;
+pub use self::image::{Image, ImageContent, ImageId, NineSlice};
 mod mesh;
 pub use self::mesh::{UIMesh, UIMeshPipe};
+mod state;
+pub use self::state::{State, Store};
+mod text;
+pub use self::text::{Text, TextAlign};
 use super::Renderer;
-use yoga::{Layout, Node};
-use anymap::AnyMap;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::rc::Rc;
+use yoga::{Context, Layout, Node};
 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+const MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Middle, MouseButton::Right];
+
+/// Passed to event handlers. Mouse button events go to the topmost element
+/// under the mouse then bubble up to its ancestors until propagation is stopped.
 #[derive(Debug)]
+pub struct EventContext {
+    mouse_position: (f32, f32),
+    button: Option<MouseButton>,
+    propagation_stopped: bool,
+}
+
+impl EventContext {
+    pub fn new(mouse_position: (f32, f32), button: Option<MouseButton>) -> Self {
+        Self {
+            mouse_position,
+            button,
+            propagation_stopped: false,
+        }
+    }
+
+    pub fn get_mouse_position(&self) -> (f32, f32) {
+        self.mouse_position
+    }
+
+    /// Button of press, release and click events.
+    pub fn get_button(&self) -> Option<MouseButton> {
+        self.button
+    }
+
+    pub fn stop_propagation(&mut self) {
+        self.propagation_stopped = true;
+    }
+
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.propagation_stopped
+    }
+}
+
+/// Event handlers own what they capture, typically `State` handles from `Gui::use_state`.
+pub type EventHandler = Box<dyn FnMut(&mut EventContext)>;
+
+fn call_handler(handler: &mut Option<EventHandler>, context: &mut EventContext) {
+    if let Some(handler) = handler {
+        handler(context);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Rect {
     pub position: (f32, f32),
     pub size: (f32, f32),
@@ -23,56 +90,202 @@ impl Rect {
     }
 }
 
-pub struct Element<'a> {
+pub struct Element {
     node: Node,
-    background_color: [f32; 3],
-    children: Vec<Element<'a>>,
-    on_mouse_enter_fn: Option<&'a Fn()>,
+    // NOTE: Text and image elements have no background unless one is set.
+    background_color: Option<[f32; 4]>,
+    style: Vec<yoga::FlexStyle>,
+    hover_style: Vec<yoga::FlexStyle>,
+    hover_background_color: Option<[f32; 4]>,
+    border_width: f32,
+    border_color: [f32; 4],
+    corner_radius: f32,
+    // NOTE: Hover styles follow the hover state of the previous frame, as they change the layout.
+    hovered: bool,
+    // Absolute position in pixels, set by `Gui::update`.
+    rect: Rect,
+    text: Option<Text>,
+    image: Option<ImageContent>,
+    children: Vec<Element>,
+    on_mouse_enter_fn: Option<EventHandler>,
+    on_mouse_leave_fn: Option<EventHandler>,
+    on_mouse_move_fn: Option<EventHandler>,
+    on_mouse_down_fn: Option<EventHandler>,
+    on_mouse_up_fn: Option<EventHandler>,
+    on_click_fn: Option<EventHandler>,
 }
 
-impl<'a> Element<'a> {
+impl Element {
     pub fn get_node_mut(&mut self) -> &mut Node {
         &mut self.node
     }
 
-    pub fn calculate_layout(&mut self) {
+    /// Lays out the tree in a `width`×`height` root, in logical pixels.
+    pub fn calculate_layout(&mut self, width: f32, height: f32) {
         self.node
-            .calculate_layout(512.0, 512.0, yoga::Direction::LTR);
+            .calculate_layout(width, height, yoga::Direction::LTR);
     }
 
     pub fn get_layout(&self) -> Layout {
         self.node.get_layout()
     }
 
-    pub fn dispatch_on_mouse_enter(&self) {
-        if let Some(on_mouse_enter_fn) = self.on_mouse_enter_fn {
-            (*on_mouse_enter_fn)();
+    pub fn get_rect(&self) -> &Rect {
+        &self.rect
+    }
+
+    pub fn get_children(&self) -> &[Element] {
+        &self.children
+    }
+
+    /// Descendant reached by following child indices from this element.
+    pub fn get_descendant(&self, path: &[usize]) -> &Element {
+        path.iter()
+            .fold(self, |element, &index| &element.children[index])
+    }
+
+    pub fn get_descendant_mut(&mut self, path: &[usize]) -> &mut Element {
+        path.iter()
+            .fold(self, |element, &index| &mut element.children[index])
+    }
+
+    fn update_rects(&mut self, parent_position: (f32, f32)) {
+        let layout = self.get_layout();
+
+        self.rect = Rect::new(
+            (
+                parent_position.0 + layout.left(),
+                parent_position.1 + layout.top(),
+            ),
+            (layout.width(), layout.height()),
+        );
+
+        let position = self.rect.position;
+        for child in &mut self.children {
+            child.update_rects(position);
+        }
+    }
+
+    /// Path of the topmost element under `position`: children are drawn over
+    /// their parent and later siblings over earlier ones.
+    pub fn hit_test(&self, position: (f32, f32)) -> Option<Vec<usize>> {
+        for (i, child) in self.children.iter().enumerate().rev() {
+            if let Some(mut path) = child.hit_test(position) {
+                path.insert(0, i);
+                return Some(path);
+            }
+        }
+
+        if self.rect.is_position_inside(position) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    pub fn get_background_color(&self) -> Option<[f32; 4]> {
+        match self.hover_background_color {
+            Some(hover_background_color) if self.hovered => Some(hover_background_color),
+            _ => self.background_color,
+        }
+    }
+
+    pub fn get_text(&self) -> Option<&Text> {
+        self.text.as_ref()
+    }
+
+    pub fn get_image(&self) -> Option<&ImageContent> {
+        self.image.as_ref()
+    }
+
+    fn set_hovered(&mut self, hovered: bool) {
+        if hovered == self.hovered {
+            return;
+        }
+
+        self.hovered = hovered;
+
+        if hovered {
+            self.node.apply_styles(&self.hover_style);
+        } else {
+            self.node.apply_styles(&self.style);
         }
     }
+
+    pub fn dispatch_on_mouse_enter(&mut self, context: &mut EventContext) {
+        call_handler(&mut self.on_mouse_enter_fn, context);
+    }
+
+    pub fn dispatch_on_mouse_leave(&mut self, context: &mut EventContext) {
+        call_handler(&mut self.on_mouse_leave_fn, context);
+    }
+
+    pub fn dispatch_on_mouse_move(&mut self, context: &mut EventContext) {
+        call_handler(&mut self.on_mouse_move_fn, context);
+    }
 }
 
-pub struct ElementBuilder<'a> {
+pub struct ElementBuilder {
     style: std::vec::Vec<yoga::FlexStyle>,
-    background_color: Option<State<[f32; 3]>>,
-    children: Vec<ElementBuilder<'a>>,
-    on_mouse_enter_fn: Option<&'a Fn()>,
+    background_color: Option<State<[f32; 4]>>,
+    hover_style: std::vec::Vec<yoga::FlexStyle>,
+    hover_background_color: Option<State<[f32; 4]>>,
+    border_width: f32,
+    border_color: [f32; 4],
+    corner_radius: f32,
+    text: Option<Text>,
+    image: Option<ImageContent>,
+    // NOTE: Applied to the text or image by `build`, whichever order they were set in.
+    font_size: Option<f32>,
+    color: Option<[f32; 4]>,
+    text_align: Option<TextAlign>,
+    text_wrap: Option<bool>,
+    nine_slice: Option<NineSlice>,
+    children: Vec<ElementBuilder>,
+    on_mouse_enter_fn: Option<EventHandler>,
+    on_mouse_leave_fn: Option<EventHandler>,
+    on_mouse_move_fn: Option<EventHandler>,
+    on_mouse_down_fn: Option<EventHandler>,
+    on_mouse_up_fn: Option<EventHandler>,
+    on_click_fn: Option<EventHandler>,
 }
 
 #[allow(clippy::new_without_default_derive)]
-impl<'a> ElementBuilder<'a> {
+impl ElementBuilder {
     pub fn new() -> Self {
         Self {
             style: Vec::new(),
             background_color: None,
+            hover_style: Vec::new(),
+            hover_background_color: None,
+            border_width: 0.0,
+            border_color: [0.0, 0.0, 0.0, 1.0],
+            corner_radius: 0.0,
+            text: None,
+            image: None,
+            font_size: None,
+            color: None,
+            text_align: None,
+            text_wrap: None,
+            nine_slice: None,
             children: Vec::with_capacity(0),
             on_mouse_enter_fn: None,
+            on_mouse_leave_fn: None,
+            on_mouse_move_fn: None,
+            on_mouse_down_fn: None,
+            on_mouse_up_fn: None,
+            on_click_fn: None,
         }
     }
 
-    pub fn background_color(mut self, background_color: Arg<[f32; 3]>) -> Self {
+    pub fn background_color(mut self, background_color: Arg<[f32; 4]>) -> Self {
         match background_color {
             Arg::Value(value) => self.background_color = Some(State::new(value)),
-            Arg::State(state) => self.background_color = Some(state)
+            Arg::State(state) => self.background_color = Some(state),
         }
         self
     }
@@ -82,24 +295,187 @@ impl<'a> ElementBuilder<'a> {
         self
     }
 
-    pub fn child(mut self, element_builder: ElementBuilder<'a>) -> Self {
+    /// Background color while the mouse is over the element, like CSS `:hover`.
+    pub fn hover_background_color(mut self, hover_background_color: Arg<[f32; 4]>) -> Self {
+        match hover_background_color {
+            Arg::Value(value) => self.hover_background_color = Some(State::new(value)),
+            Arg::State(state) => self.hover_background_color = Some(state),
+        }
+        self
+    }
+
+    /// Styles applied over `style` while the mouse is over the element, like CSS `:hover`.
+    /// `style` is applied again when the mouse leaves, so it must also set
+    /// every property changed here.
+    pub fn hover_style(mut self, hover_style: &mut std::vec::Vec<yoga::FlexStyle>) -> Self {
+        self.hover_style.append(hover_style);
+        self
+    }
+
+    /// Border on every edge, inside the element like a yoga border so it pads the children.
+    pub fn border_width(mut self, border_width: f32) -> Self {
+        self.border_width = border_width;
+        self.style.push(yoga::FlexStyle::Border(border_width.into()));
+        self
+    }
+
+    pub fn border_color(mut self, border_color: Arg<[f32; 4]>) -> Self {
+        self.border_color = match border_color {
+            Arg::Value(value) => value,
+            Arg::State(state) => state.get(),
+        };
+        self
+    }
+
+    /// Radius of the corners of the background and border, in pixels.
+    pub fn corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    /// Makes this a text element, sized to its text. Text elements can't have children.
+    pub fn text(mut self, text: Text) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Only for text elements.
+    pub fn font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    /// Color of the text, or tint of the image. Only for text and image elements.
+    pub fn color(mut self, color: Arg<[f32; 4]>) -> Self {
+        self.color = Some(match color {
+            Arg::Value(value) => value,
+            Arg::State(state) => state.get(),
+        });
+        self
+    }
+
+    /// Only for text elements.
+    pub fn text_align(mut self, align: TextAlign) -> Self {
+        self.text_align = Some(align);
+        self
+    }
+
+    /// Only for text elements.
+    pub fn text_wrap(mut self, wrap: bool) -> Self {
+        self.text_wrap = Some(wrap);
+        self
+    }
+
+    /// Makes this an image element, sized like the image unless styled otherwise.
+    /// Image elements can't have children.
+    pub fn image(mut self, image: ImageContent) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Only for image elements.
+    pub fn nine_slice(mut self, nine_slice: NineSlice) -> Self {
+        self.nine_slice = Some(nine_slice);
+        self
+    }
+
+    pub fn child(mut self, element_builder: ElementBuilder) -> Self {
         self.children.push(element_builder);
         self
     }
 
-    pub fn on_mouse_enter(mut self, callback: &'a Fn()) -> Self {
-        self.on_mouse_enter_fn = Some(callback);
+    pub fn on_mouse_enter<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_mouse_enter_fn = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_mouse_leave<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_mouse_leave_fn = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_mouse_move<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_mouse_move_fn = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_mouse_down<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_mouse_down_fn = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_mouse_up<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_mouse_up_fn = Some(Box::new(callback));
+        self
+    }
+
+    /// Called when a button is pressed then released over the element.
+    pub fn on_click<F: FnMut(&mut EventContext) + 'static>(mut self, callback: F) -> Self {
+        self.on_click_fn = Some(Box::new(callback));
         self
     }
 
-    pub fn build(self) -> Element<'a> {
+    /// Panics when a text or image element has children, when text or image
+    /// modifiers are used on other elements, or when `hover_style` sets a
+    /// property `style` doesn't.
+    pub fn build(mut self) -> Element {
+        assert!(
+            self.children.is_empty() || (self.text.is_none() && self.image.is_none()),
+            "Text and image elements can't have children"
+        );
+        assert!(
+            self.text.is_some()
+                || (self.font_size.is_none()
+                    && self.text_align.is_none()
+                    && self.text_wrap.is_none()),
+            "font_size, text_align and text_wrap are only for text elements"
+        );
+        assert!(
+            self.image.is_some() || self.nine_slice.is_none(),
+            "nine_slice is only for image elements"
+        );
+        assert!(
+            self.text.is_some() || self.image.is_some() || self.color.is_none(),
+            "color is only for text and image elements, see background_color"
+        );
+        assert!(
+            self.hover_style.iter().all(|hover_style| self
+                .style
+                .iter()
+                .any(|style| mem::discriminant(style) == mem::discriminant(hover_style))),
+            "hover_style can only change properties set by style"
+        );
+
+        if let Some(ref mut text) = self.text {
+            text.font_size = self.font_size.unwrap_or(text.font_size);
+            text.color = self.color.unwrap_or(text.color);
+            text.align = self.text_align.unwrap_or(text.align);
+            text.wrap = self.text_wrap.unwrap_or(text.wrap);
+        }
+
+        if let Some(ref mut image) = self.image {
+            image.color = self.color.unwrap_or(image.color);
+            image.nine_slice = self.nine_slice.or(image.nine_slice);
+        }
+
         let mut node = Node::new();
 
         node.apply_styles(&self.style);
 
+        if let Some(ref text) = self.text {
+            node.set_measure_func(Some(text::measure_text));
+            node.set_context(Some(Context::new(text.clone())));
+        }
+
+        if let Some(ref image) = self.image {
+            node.set_measure_func(Some(image::measure_image));
+            node.set_context(Some(Context::new(image.clone())));
+        }
+
         let background_color = match self.background_color {
-            Some(background_color) => background_color.value,
-            None => [1.0, 1.0, 1.0],
+            Some(background_color) => Some(background_color.get()),
+            None if self.text.is_some() || self.image.is_some() => None,
+            None => Some([1.0, 1.0, 1.0, 1.0]),
         };
 
         let mut i = 0;
@@ -119,115 +495,591 @@ impl<'a> ElementBuilder<'a> {
         Element {
             node,
             background_color,
+            style: self.style,
+            hover_style: self.hover_style,
+            hover_background_color: self.hover_background_color.map(|state| state.get()),
+            border_width: self.border_width,
+            border_color: self.border_color,
+            corner_radius: self.corner_radius,
+            hovered: false,
+            rect: Rect::new((0.0, 0.0), (0.0, 0.0)),
+            text: self.text,
+            image: self.image,
             children,
             on_mouse_enter_fn: self.on_mouse_enter_fn,
+            on_mouse_leave_fn: self.on_mouse_leave_fn,
+            on_mouse_move_fn: self.on_mouse_move_fn,
+            on_mouse_down_fn: self.on_mouse_down_fn,
+            on_mouse_up_fn: self.on_mouse_up_fn,
+            on_click_fn: self.on_click_fn,
         }
     }
 }
 
 pub enum Arg<T> {
     Value(T),
-    State(State<T>)
+    State(State<T>),
 }
 
-pub struct State<T> {
-    value: T,
+pub struct Gui {
+    store: Store,
+    // Logical size of the window the root element fills.
+    size: (f32, f32),
+    hidpi_factor: f32,
+    font: Rc<FontAtlas>,
+    images: Vec<Rc<Image>>,
+    mouse_position: (f32, f32),
+    last_mouse_position: (f32, f32),
+    // Child index paths from the root of the elements under the mouse last frame.
+    hovered_paths: HashSet<Vec<usize>>,
+    buttons_down: HashSet<MouseButton>,
+    last_buttons_down: HashSet<MouseButton>,
+    // Element each held button was pressed on, a click needs the release over it.
+    pressed_paths: HashMap<MouseButton, Vec<usize>>,
+    // NOTE: Created by the first render, layout and input don't need a renderer.
+    mesh: Option<UIMesh>,
 }
 
-impl<T> State<T> {
-    pub fn new(value: T) -> Self {
-        Self {
-            value,
-        }
-    }
+fn bubble_event(
+    root: &mut Element,
+    path: &[usize],
+    context: &mut EventContext,
+    get_handler: fn(&mut Element) -> &mut Option<EventHandler>,
+) {
+    for depth in (0..=path.len()).rev() {
+        call_handler(
+            get_handler(root.get_descendant_mut(&path[..depth])),
+            context,
+        );
 
-    pub fn set(&mut self, value: T) {
-        self.value = value;
+        if context.is_propagation_stopped() {
+            return;
+        }
     }
 }
 
-pub struct Gui {
-    // states: Vec<State<>>
-    mouse_position: (f32, f32),
-}
-
 #[allow(clippy::new_without_default_derive)]
 impl Gui {
     pub fn new() -> Self {
         Self {
+            store: Store::new(),
+            size: (0.0, 0.0),
+            hidpi_factor: 1.0,
+            font: Rc::new(FontAtlas::default()),
+            images: Vec::new(),
             mouse_position: (0.0, 0.0),
+            last_mouse_position: (0.0, 0.0),
+            hovered_paths: HashSet::new(),
+            buttons_down: HashSet::new(),
+            last_buttons_down: HashSet::new(),
+            pressed_paths: HashMap::new(),
+            mesh: None,
         }
     }
 
-    pub fn render(&self, mut renderer: &mut Renderer, pipe: &UIMeshPipe, mut element: Element) {
-        element.calculate_layout();
-        self.render_children(&mut renderer, pipe, element);
+    /// Lays `element` out and dispatches the input received since the last update.
+    pub fn update(&mut self, element: &mut Element) {
+        self.layout(element);
+        self.dispatch_input(element);
     }
 
-    pub fn render_children(&self, mut renderer: &mut Renderer, pipe: &UIMeshPipe, element: Element) {
-        let layout = element.get_layout();
-        let rect = Rect {
-            position: (layout.left(), layout.top()),
-            size: (layout.width(), layout.height()),
-        };
+    /// Computes the rect of every element, with the hover styles of the last update.
+    pub fn layout(&self, element: &mut Element) {
+        self.apply_hover(element, &mut Vec::new());
+        element.calculate_layout(self.size.0, self.size.1);
+        element.update_rects((0.0, 0.0));
+    }
+
+    /// Dispatches the input received since the last update to the laid out `element`.
+    pub fn dispatch_input(&mut self, element: &mut Element) {
+        let mut hovered_paths = HashSet::new();
+
+        self.dispatch_hover(element, &mut Vec::new(), &mut hovered_paths);
+        self.dispatch_buttons(element);
+
+        self.hovered_paths = hovered_paths;
+        self.last_mouse_position = self.mouse_position;
+        self.last_buttons_down = self.buttons_down.clone();
+    }
+
+    fn apply_hover(&self, element: &mut Element, path: &mut Vec<usize>) {
+        element.set_hovered(self.hovered_paths.contains(path));
+
+        for (i, child) in element.children.iter_mut().enumerate() {
+            path.push(i);
+            self.apply_hover(child, path);
+            path.pop();
+        }
+    }
+
+    fn dispatch_hover(
+        &self,
+        element: &mut Element,
+        path: &mut Vec<usize>,
+        hovered_paths: &mut HashSet<Vec<usize>>,
+    ) {
+        let was_hovered = self.hovered_paths.contains(path);
+        let is_hovered = element.rect.is_position_inside(self.mouse_position);
+        let mut context = EventContext::new(self.mouse_position, None);
 
-        if rect.is_position_inside(self.mouse_position) {
-            element.dispatch_on_mouse_enter();
+        if is_hovered {
+            hovered_paths.insert(path.clone());
+
+            if !was_hovered {
+                element.dispatch_on_mouse_enter(&mut context);
+            } else if self.mouse_position != self.last_mouse_position {
+                element.dispatch_on_mouse_move(&mut context);
+            }
+        } else if was_hovered {
+            element.dispatch_on_mouse_leave(&mut context);
+        }
+
+        for (i, child) in element.children.iter_mut().enumerate() {
+            path.push(i);
+            self.dispatch_hover(child, path, hovered_paths);
+            path.pop();
+        }
+    }
+
+    fn dispatch_buttons(&mut self, element: &mut Element) {
+        let target = element.hit_test(self.mouse_position);
+
+        for &button in &MOUSE_BUTTONS {
+            let is_down = self.buttons_down.contains(&button);
+            let was_down = self.last_buttons_down.contains(&button);
+
+            if is_down && !was_down {
+                if let Some(ref target) = target {
+                    let mut context = EventContext::new(self.mouse_position, Some(button));
+                    bubble_event(element, target, &mut context, |element| {
+                        &mut element.on_mouse_down_fn
+                    });
+                    self.pressed_paths.insert(button, target.clone());
+                }
+            } else if !is_down && was_down {
+                let pressed_path = self.pressed_paths.remove(&button);
+
+                if let Some(ref target) = target {
+                    let mut context = EventContext::new(self.mouse_position, Some(button));
+                    bubble_event(element, target, &mut context, |element| {
+                        &mut element.on_mouse_up_fn
+                    });
+
+                    // NOTE: Like in browsers, the click goes to the deepest element under both.
+                    if let Some(pressed_path) = pressed_path {
+                        let common_depth = pressed_path
+                            .iter()
+                            .zip(target)
+                            .take_while(|(pressed, released)| pressed == released)
+                            .count();
+                        let mut context = EventContext::new(self.mouse_position, Some(button));
+                        bubble_event(element, &target[..common_depth], &mut context, |element| {
+                            &mut element.on_click_fn
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends the quads of the laid out `element` to `draw_list`, parents before children.
+    pub fn build_draw_list(&self, element: &Element, draw_list: &mut DrawList) {
+        let background_color = element.get_background_color();
+        if background_color.is_some() || element.border_width > 0.0 {
+            draw_list.push_rounded_quad(
+                element.rect.clone(),
+                background_color.unwrap_or([0.0; 4]),
+                element.corner_radius,
+                element.border_width,
+                element.border_color,
+            );
+        }
+
+        if let Some(ref text) = element.text {
+            for (rect, uv) in text.layout_glyphs(&element.rect) {
+                draw_list.push_glyph_quad(rect, text.color, uv);
+            }
         }
 
-        let mut mesh = UIMesh::new(&mut renderer, &rect, element.background_color);
-        renderer.draw(&mut mesh, pipe);
+        if let Some(ref image) = element.image {
+            for (rect, uv) in image.layout_quads(&element.rect) {
+                draw_list.push_image_quad(rect, image.color, image.id, uv);
+            }
+        }
 
-        for child in element.children {
-            self.render_children(&mut renderer, pipe, child);
+        for child in &element.children {
+            self.build_draw_list(child, draw_list);
         }
     }
 
-    pub fn create_element<'a>() -> ElementBuilder<'a> {
+    /// Draws `draw_list` blended over the frame, with one draw call per run of
+    /// quads using the same texture.
+    pub fn render(&mut self, renderer: &mut Renderer, pipe: &UIMeshPipe, draw_list: &DrawList) {
+        let font = &self.font;
+        let mesh = self.mesh.get_or_insert_with(|| UIMesh::new(renderer, font));
+        mesh.render(renderer, pipe, draw_list, &self.images, self.hidpi_factor);
+    }
+
+    pub fn create_element() -> ElementBuilder {
         ElementBuilder::new()
     }
 
-    pub fn use_state<T>(&mut self, value: T) -> State<T> {
-        State::new(value)
+    /// To be called before building the tree of each frame, see `Store::new_frame`.
+    pub fn new_frame(&mut self) {
+        self.store.new_frame();
+    }
+
+    /// Returns a state persisted across frames, see `Store::use_state`.
+    pub fn use_state<T: 'static>(&mut self, initial_value: T) -> State<T> {
+        self.store.use_state(initial_value)
+    }
+
+    /// Font of the text elements created from now on.
+    pub fn set_font(&mut self, font: FontAtlas) {
+        self.font = Rc::new(font);
+        // NOTE: The mesh holds the atlas texture, the next render creates a new one.
+        self.mesh = None;
+    }
+
+    pub fn get_font(&self) -> Rc<FontAtlas> {
+        self.font.clone()
+    }
+
+    pub fn create_text(&self, content: &str) -> ElementBuilder {
+        Gui::create_element().text(Text::new(self.get_font(), content))
+    }
+
+    /// Keeps `image` for image elements, its texture is created by the next render.
+    pub fn add_image(&mut self, image: Image) -> ImageId {
+        self.images.push(Rc::new(image));
+        ImageId(self.images.len() - 1)
+    }
+
+    pub fn get_image(&self, id: ImageId) -> Rc<Image> {
+        self.images[id.0].clone()
+    }
+
+    pub fn create_image(&self, id: ImageId) -> ElementBuilder {
+        Gui::create_element().image(ImageContent::new(id, self.images[id.0].get_size()))
     }
 
-    // pub fn set_state<T>(&mut self, value: T) {
+    /// Sets the logical size of the window, the next `layout` fills it.
+    pub fn resize(&mut self, width: f32, height: f32, hidpi_factor: f32) {
+        self.size = (width, height);
+        self.hidpi_factor = hidpi_factor;
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
 
-    // }
+    pub fn get_hidpi_factor(&self) -> f32 {
+        self.hidpi_factor
+    }
 
     pub fn set_mouse_position(&mut self, x: f32, y: f32) {
         self.mouse_position = (x, y);
     }
-}
 
-struct Store {
-    stores: AnyMap,
+    pub fn set_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+        if pressed {
+            self.buttons_down.insert(button);
+        } else {
+            self.buttons_down.remove(&button);
+        }
+    }
 }
 
-impl Store {
-    pub fn new() -> Self {
-        Self {
-            stores: AnyMap::new(),
-        }
+#[test]
+fn test_use_state_persists_across_rebuilds() {
+    let mut gui = Gui::new();
+
+    for frame in 0..3 {
+        gui.new_frame();
+
+        let color_state = gui.use_state([1.0, 0.0, 1.0, 1.0]);
+        let clicks = gui.use_state(0);
+
+        assert_eq!(clicks.get(), frame);
+        clicks.set(frame + 1);
+        color_state.set([1.0, 0.3, 1.0, 1.0]);
     }
 
-    pub fn add_state<T>(&mut self, state: State<T>) -> u32 {
-        let state_store_opt = self.stores.get_mut::<Vec<State<T>>>();
+    gui.new_frame();
+    assert_eq!(gui.use_state([0.0; 4]).get(), [1.0, 0.3, 1.0, 1.0]);
+}
+
+#[cfg(test)]
+fn click(gui: &mut Gui, element: &mut Element, position: (f32, f32)) {
+    gui.set_mouse_position(position.0, position.1);
+    gui.set_mouse_button(MouseButton::Left, true);
+    gui.update(element);
+    gui.set_mouse_button(MouseButton::Left, false);
+    gui.update(element);
+}
 
-        if let Some(state_store) = state_store_opt {
-            state_store.push(state);
-            state_store.len() as u32 - 1
-        } else {
-            let new_store = Vec::new();
+#[cfg(test)]
+fn square(size: i32) -> Vec<yoga::FlexStyle> {
+    use yoga::prelude::*;
 
-            new_store.push(state);
-            self.stores.insert(new_store);
-            0
-        }
+    vec![
+        yoga::FlexStyle::Width(size.point()),
+        yoga::FlexStyle::Height(size.point()),
+    ]
+}
+
+#[cfg(test)]
+fn counter(count: &State<i32>) -> impl FnMut(&mut EventContext) {
+    let count = count.clone();
+    move |_| count.update(|count| *count += 1)
+}
+
+#[test]
+fn test_click_bubbles_to_parents() {
+    let child_clicks = State::new(0);
+    let parent_clicks = State::new(0);
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .on_click(counter(&parent_clicks))
+        .child(
+            Gui::create_element()
+                .style(&mut square(50))
+                .on_click(counter(&child_clicks)),
+        )
+        .build();
+
+    click(&mut gui, &mut element, (25.0, 25.0));
+    assert_eq!((child_clicks.get(), parent_clicks.get()), (1, 1));
+
+    click(&mut gui, &mut element, (75.0, 75.0));
+    assert_eq!((child_clicks.get(), parent_clicks.get()), (1, 2));
+}
+
+#[test]
+fn test_stop_propagation() {
+    let parent_downs = State::new(0);
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .on_mouse_down(counter(&parent_downs))
+        .child(
+            Gui::create_element()
+                .style(&mut square(50))
+                .on_mouse_down(|context| context.stop_propagation()),
+        )
+        .build();
+
+    click(&mut gui, &mut element, (25.0, 25.0));
+    assert_eq!(parent_downs.get(), 0);
+
+    click(&mut gui, &mut element, (75.0, 75.0));
+    assert_eq!(parent_downs.get(), 1);
+}
+
+#[test]
+fn test_topmost_sibling_wins() {
+    use yoga::{FlexStyle, PositionType};
+
+    let first_clicks = State::new(0);
+    let second_clicks = State::new(0);
+    let overlapping = || {
+        let mut style = square(50);
+        style.push(FlexStyle::Position(PositionType::Absolute));
+        style
+    };
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .child(
+            Gui::create_element()
+                .style(&mut overlapping())
+                .on_click(counter(&first_clicks)),
+        )
+        .child(
+            Gui::create_element()
+                .style(&mut overlapping())
+                .on_click(counter(&second_clicks)),
+        )
+        .build();
+
+    click(&mut gui, &mut element, (25.0, 25.0));
+    assert_eq!((first_clicks.get(), second_clicks.get()), (0, 1));
+}
+
+#[test]
+fn test_click_needs_release_over_the_element() {
+    let clicks = State::new(0);
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .child(
+            Gui::create_element()
+                .style(&mut square(50))
+                .on_click(counter(&clicks)),
+        )
+        .build();
+
+    gui.set_mouse_position(25.0, 25.0);
+    gui.set_mouse_button(MouseButton::Left, true);
+    gui.update(&mut element);
+    gui.set_mouse_position(75.0, 75.0);
+    gui.set_mouse_button(MouseButton::Left, false);
+    gui.update(&mut element);
+
+    assert_eq!(clicks.get(), 0);
+}
+
+#[test]
+fn test_mouse_enter_and_leave_fire_once() {
+    let enters = State::new(0);
+    let leaves = State::new(0);
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(100))
+        .on_mouse_enter(counter(&enters))
+        .on_mouse_leave(counter(&leaves))
+        .build();
+
+    for &position in &[(50.0, 50.0), (60.0, 60.0), (200.0, 200.0), (300.0, 300.0)] {
+        gui.set_mouse_position(position.0, position.1);
+        gui.update(&mut element);
     }
+
+    assert_eq!((enters.get(), leaves.get()), (1, 1));
+}
+
+#[test]
+fn test_layout_without_renderer() {
+    use yoga::prelude::*;
+    use yoga::{FlexDirection, FlexStyle};
+
+    let gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut vec![
+            FlexStyle::FlexDirection(FlexDirection::Row),
+            FlexStyle::Padding(10.point()),
+        ])
+        .child(Gui::create_element().style(&mut square(32)))
+        .child(Gui::create_element().style(&mut square(16)))
+        .build();
+
+    gui.layout(&mut element);
+
+    let children = element.get_children();
+    assert_eq!(
+        children[0].get_rect(),
+        &Rect::new((10.0, 10.0), (32.0, 32.0))
+    );
+    assert_eq!(
+        children[1].get_rect(),
+        &Rect::new((42.0, 10.0), (16.0, 16.0))
+    );
+}
+
+#[test]
+fn test_root_fills_window() {
+    use yoga::prelude::*;
+    use yoga::FlexStyle;
+
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .child(Gui::create_element().style(&mut vec![
+            FlexStyle::Width(50.percent()),
+            FlexStyle::Height(25.percent()),
+        ]))
+        .build();
+
+    gui.resize(800.0, 600.0, 2.0);
+    gui.layout(&mut element);
+    assert_eq!(element.get_rect(), &Rect::new((0.0, 0.0), (800.0, 600.0)));
+    assert_eq!(
+        element.get_children()[0].get_rect(),
+        &Rect::new((0.0, 0.0), (400.0, 150.0))
+    );
+
+    gui.resize(400.0, 300.0, 2.0);
+    gui.layout(&mut element);
+    assert_eq!(
+        element.get_children()[0].get_rect(),
+        &Rect::new((0.0, 0.0), (200.0, 75.0))
+    );
 }
 
 #[test]
-fn test_store() {
+fn test_hover_style_is_drawn_after_hover() {
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(50))
+        .background_color(Arg::Value([0.0, 1.0, 0.0, 1.0]))
+        .hover_background_color(Arg::Value([0.0, 0.0, 1.0, 1.0]))
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.set_mouse_position(25.0, 25.0);
+    gui.update(&mut element);
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+
+    assert_eq!(draw_list.quads[0].color, [0.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_hover_style_is_undone_after_leave() {
+    let mut gui = Gui::new();
+    let mut element = Gui::create_element()
+        .style(&mut square(50))
+        .hover_style(&mut square(80))
+        .build();
+
+    gui.set_mouse_position(25.0, 25.0);
+    gui.update(&mut element);
+    gui.layout(&mut element);
+    assert_eq!(element.get_rect().size, (80.0, 80.0));
+
+    gui.set_mouse_position(200.0, 200.0);
+    gui.update(&mut element);
+    gui.layout(&mut element);
+    assert_eq!(element.get_rect().size, (50.0, 50.0));
+}
+
+#[test]
+#[should_panic(expected = "hover_style can only change properties set by style")]
+fn test_hover_only_property() {
+    use yoga::prelude::*;
+    use yoga::FlexStyle;
+
+    // Leaving the element could not undo a padding the base style doesn't set.
+    Gui::create_element()
+        .style(&mut square(50))
+        .hover_style(&mut vec![FlexStyle::Padding(10.point())])
+        .build();
+}
+
+#[test]
+fn test_handlers_mutate_used_state() {
+    let mut gui = Gui::new();
+    let build = |gui: &mut Gui| {
+        let color_state = gui.use_state([1.0, 0.0, 1.0, 1.0]);
+        let handler_color_state = color_state.clone();
+
+        Gui::create_element()
+            .style(&mut square(100))
+            .background_color(Arg::State(color_state))
+            .on_click(move |_| handler_color_state.set([1.0, 0.3, 1.0, 1.0]))
+            .build()
+    };
+
+    gui.new_frame();
+    let mut element = build(&mut gui);
+    click(&mut gui, &mut element, (50.0, 50.0));
 
+    gui.new_frame();
+    let element = build(&mut gui);
+    assert_eq!(element.get_background_color(), Some([1.0, 0.3, 1.0, 1.0]));
 }
@@ -0,0 +1,116 @@
+use anymap::AnyMap;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Shared handle to a value kept by the `Store`, cloning it does not copy the value.
+pub struct State<T> {
+    value: Rc<RefCell<T>>,
+}
+
+impl<T> State<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: Rc::new(RefCell::new(value)),
+        }
+    }
+
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.value.borrow().clone()
+    }
+
+    pub fn set(&self, value: T) {
+        *self.value.borrow_mut() = value;
+    }
+
+    pub fn update<F: FnOnce(&mut T)>(&self, update: F) {
+        update(&mut self.value.borrow_mut());
+    }
+}
+
+impl<T> Clone for State<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// States persisted across frames, keyed by the order in which they are used
+/// while building the tree, like hooks.
+pub struct Store {
+    // One `HashMap<usize, State<T>>` per state type.
+    states: AnyMap,
+    cursor: usize,
+}
+
+#[allow(clippy::new_without_default_derive)]
+impl Store {
+    pub fn new() -> Self {
+        Self {
+            states: AnyMap::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Starts a new frame, states are then used again from the first one.
+    pub fn new_frame(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Returns the state used at the same point of the previous frames, or a
+    /// new one holding `initial_value`.
+    pub fn use_state<T: 'static>(&mut self, initial_value: T) -> State<T> {
+        let key = self.cursor;
+        self.cursor += 1;
+
+        self.states
+            .entry::<HashMap<usize, State<T>>>()
+            .or_insert_with(HashMap::new)
+            .entry(key)
+            .or_insert_with(|| State::new(initial_value))
+            .clone()
+    }
+}
+
+#[test]
+fn test_store() {
+    let mut store = Store::new();
+
+    let counter = store.use_state(0);
+    let name = store.use_state("first".to_string());
+    counter.set(3);
+    name.update(|name| name.push_str(" frame"));
+
+    store.new_frame();
+
+    let counter = store.use_state(0);
+    let name = store.use_state("second".to_string());
+    assert_eq!(counter.get(), 3);
+    assert_eq!(name.get(), "first frame");
+}
+
+#[test]
+fn test_store_new_states() {
+    let mut store = Store::new();
+
+    store.use_state(1);
+    store.new_frame();
+    store.use_state(1);
+    let added = store.use_state(2);
+
+    assert_eq!(added.get(), 2);
+}
+
+#[test]
+fn test_state_handles_are_shared() {
+    let state = State::new([1.0, 0.0, 1.0]);
+    let handle = state.clone();
+
+    handle.set([1.0, 0.3, 1.0]);
+
+    assert_eq!(state.get(), [1.0, 0.3, 1.0]);
+}
@@ -0,0 +1,274 @@
+use super::{FontAtlas, Rect};
+use std::rc::Rc;
+use yoga::{MeasureMode, Node, NodeRef, Size};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Content of a text element, also the context of its yoga node for measuring.
+#[derive(Clone, Debug)]
+pub struct Text {
+    pub font: Rc<FontAtlas>,
+    pub content: String,
+    pub font_size: f32,
+    pub color: [f32; 4],
+    pub align: TextAlign,
+    /// Breaks lines between words to fit the width of the element.
+    pub wrap: bool,
+}
+
+impl Text {
+    pub fn new(font: Rc<FontAtlas>, content: &str) -> Self {
+        Self {
+            font,
+            content: content.to_string(),
+            font_size: 16.0,
+            color: [0.0, 0.0, 0.0, 1.0],
+            align: TextAlign::Left,
+            wrap: true,
+        }
+    }
+
+    fn get_scale(&self) -> f32 {
+        self.font_size / self.font.get_font_size()
+    }
+
+    fn get_line_height(&self) -> f32 {
+        self.font.get_line_height() * self.get_scale()
+    }
+
+    fn get_width(&self, text: &str) -> f32 {
+        text.chars()
+            .filter_map(|c| self.font.get_glyph(c))
+            .map(|glyph| glyph.advance)
+            .sum::<f32>()
+            * self.get_scale()
+    }
+
+    /// Lines with their width, broken on `\n` and, when wrapping, on the last
+    /// space that fits `max_width`. Words wider than `max_width` are not split.
+    pub fn break_lines(&self, max_width: Option<f32>) -> Vec<(&str, f32)> {
+        let max_width = if self.wrap { max_width } else { None };
+        let space_width = self.get_width(" ");
+        let mut lines = Vec::new();
+
+        for paragraph in self.content.split('\n') {
+            let mut line: Option<(usize, usize, f32)> = None;
+            let mut word_start = 0;
+
+            for word in paragraph.split(' ') {
+                let word_end = word_start + word.len();
+                let word_width = self.get_width(word);
+
+                line = match line {
+                    Some((start, end, width)) => {
+                        let next_width = width + space_width + word_width;
+
+                        if max_width.map_or(false, |max_width| next_width > max_width) {
+                            lines.push((&paragraph[start..end], width));
+                            Some((word_start, word_end, word_width))
+                        } else {
+                            Some((start, word_end, next_width))
+                        }
+                    }
+                    None => Some((word_start, word_end, word_width)),
+                };
+                word_start = word_end + 1;
+            }
+
+            if let Some((start, end, width)) = line {
+                lines.push((&paragraph[start..end], width));
+            }
+        }
+
+        lines
+    }
+
+    pub fn measure(&self, max_width: Option<f32>) -> (f32, f32) {
+        let lines = self.break_lines(max_width);
+        let width = lines.iter().map(|&(_, width)| width).fold(0.0, f32::max);
+
+        (width, lines.len() as f32 * self.get_line_height())
+    }
+
+    /// Glyph quads and their texture coordinates for the text laid out in `rect`.
+    pub fn layout_glyphs(&self, rect: &Rect) -> Vec<(Rect, Rect)> {
+        let scale = self.get_scale();
+        let line_height = self.get_line_height();
+        let mut quads = Vec::new();
+
+        for (i, (line, width)) in self.break_lines(Some(rect.size.0)).into_iter().enumerate() {
+            let mut x = rect.position.0
+                + match self.align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => (rect.size.0 - width) / 2.0,
+                    TextAlign::Right => rect.size.0 - width,
+                };
+            let y = rect.position.1 + i as f32 * line_height;
+
+            for glyph in line.chars().filter_map(|c| self.font.get_glyph(c)) {
+                if glyph.size.0 > 0.0 && glyph.size.1 > 0.0 {
+                    quads.push((
+                        Rect::new(
+                            (x + glyph.offset.0 * scale, y + glyph.offset.1 * scale),
+                            (glyph.size.0 * scale, glyph.size.1 * scale),
+                        ),
+                        glyph.uv.clone(),
+                    ));
+                }
+
+                x += glyph.advance * scale;
+            }
+        }
+
+        quads
+    }
+}
+
+/// Yoga measure function of text nodes, whose context is their `Text`.
+pub extern "C" fn measure_text(
+    node_ref: NodeRef,
+    width: f32,
+    width_mode: MeasureMode,
+    _height: f32,
+    _height_mode: MeasureMode,
+) -> Size {
+    let text = Node::get_context(&node_ref).and_then(|context| context.downcast_ref::<Text>());
+    let max_width = match width_mode {
+        MeasureMode::Undefined => None,
+        MeasureMode::Exactly | MeasureMode::AtMost => Some(width),
+    };
+    let (width, height) = text.map_or((0.0, 0.0), |text| text.measure(max_width));
+
+    // NOTE: Rounded up so snapping the layout to whole pixels never wraps the text again.
+    Size {
+        width: width.ceil(),
+        height: height.ceil(),
+    }
+}
+
+#[cfg(test)]
+pub fn monospace_font() -> FontAtlas {
+    use super::Glyph;
+
+    // Glyphs advance by 10 pixels at a font size of 20, lines are 24 pixels high.
+    let mut font = FontAtlas::new(20.0, 24.0, (64, 64));
+    for c in (b' '..=b'~').map(char::from) {
+        let size = if c == ' ' { (0.0, 0.0) } else { (8.0, 16.0) };
+
+        font.insert_glyph(
+            c,
+            Glyph {
+                advance: 10.0,
+                offset: (1.0, 4.0),
+                size,
+                uv: Rect::new((0.5, 0.5), (0.125, 0.25)),
+            },
+        );
+    }
+
+    font
+}
+
+#[test]
+fn test_break_lines() {
+    let mut text = Text::new(Rc::new(monospace_font()), "one two three\nfour");
+
+    assert_eq!(
+        text.break_lines(Some(80.0)),
+        vec![("one two", 70.0), ("three", 50.0), ("four", 40.0)]
+    );
+    assert_eq!(text.measure(Some(80.0)), (70.0, 72.0));
+
+    text.wrap = false;
+    assert_eq!(text.measure(Some(80.0)), (130.0, 48.0));
+}
+
+#[test]
+fn test_layout_glyphs() {
+    let mut text = Text::new(Rc::new(monospace_font()), "a b");
+    text.font_size = 10.0;
+    text.align = TextAlign::Right;
+
+    let quads = text.layout_glyphs(&Rect::new((100.0, 50.0), (40.0, 12.0)));
+
+    // Half scale, the 15 pixels wide line ends on the right edge and the space has no quad.
+    assert_eq!(quads.len(), 2);
+    assert_eq!(quads[0].0, Rect::new((125.5, 52.0), (4.0, 8.0)));
+    assert_eq!(quads[1].0, Rect::new((135.5, 52.0), (4.0, 8.0)));
+}
+
+#[test]
+fn test_text_measures_itself() {
+    use super::{DrawList, Gui};
+    use yoga::prelude::*;
+    use yoga::{Align, FlexStyle};
+
+    let mut gui = Gui::new();
+    gui.set_font(monospace_font());
+    let mut element = Gui::create_element()
+        .style(&mut vec![
+            FlexStyle::Width(100.point()),
+            FlexStyle::AlignItems(Align::FlexStart),
+        ])
+        .child(gui.create_text("one two three").font_size(10.0))
+        .child(
+            gui.create_text("one two three")
+                .font_size(10.0)
+                .text_wrap(false),
+        )
+        .build();
+    let mut draw_list = DrawList::new();
+
+    gui.layout(&mut element);
+    gui.build_draw_list(&element, &mut draw_list);
+
+    let children = element.get_children();
+    assert_eq!(children[0].get_rect().size, (65.0, 12.0));
+    assert_eq!(children[1].get_rect().size, (65.0, 12.0));
+    // The white root and one quad per visible glyph, text has no background.
+    assert_eq!(draw_list.quads.len(), 1 + 11 + 11);
+    assert!(draw_list.quads[1..].iter().all(|quad| quad.uv.is_some()));
+
+    let mut element = Gui::create_element()
+        .style(&mut vec![
+            FlexStyle::Width(50.point()),
+            FlexStyle::AlignItems(Align::FlexStart),
+        ])
+        .child(gui.create_text("one two three").font_size(10.0))
+        .build();
+    gui.layout(&mut element);
+    assert_eq!(element.get_children()[0].get_rect().size, (35.0, 24.0));
+}
+
+#[test]
+fn test_modifiers_before_content() {
+    use super::{Arg, Gui};
+
+    let gui = Gui::new();
+    let element = Gui::create_element()
+        .font_size(10.0)
+        .color(Arg::Value([1.0, 0.0, 0.0, 1.0]))
+        .text_wrap(false)
+        .text(Text::new(gui.get_font(), "a"))
+        .build();
+
+    let text = element.get_text().unwrap();
+    assert_eq!(text.font_size, 10.0);
+    assert_eq!(text.color, [1.0, 0.0, 0.0, 1.0]);
+    assert!(!text.wrap);
+}
+
+#[test]
+#[should_panic(expected = "Text and image elements can't have children")]
+fn test_text_without_children() {
+    use super::Gui;
+
+    let gui = Gui::new();
+
+    gui.create_text("a").child(Gui::create_element()).build();
+}
@@ -0,0 +1,79 @@
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+// NOTE: Mesa's GL_UNSIGNED_BYTE, the type of the channels of the default framebuffer.
+const UNSIGNED_BYTE: u32 = 0x1401;
+
+/// OpenGL context rendering in memory through OSMesa, which needs neither a
+/// display server nor a GPU.
+pub struct HeadlessContext {
+    context: osmesa_sys::OSMesaContext,
+    // NOTE: Backs the default framebuffer, the renderer draws into its own textures.
+    buffer: Vec<u32>,
+    size: (u32, u32),
+}
+
+impl HeadlessContext {
+    pub fn new(width: u32, height: u32, gl_version: (u8, u8)) -> Result<Self, String> {
+        osmesa_sys::OsMesa::try_loading()
+            .map_err(|error| format!("Failed to load OSMesa: {:?}", error))?;
+
+        let attributes = [
+            osmesa_sys::OSMESA_PROFILE,
+            osmesa_sys::OSMESA_CORE_PROFILE,
+            osmesa_sys::OSMESA_CONTEXT_MAJOR_VERSION,
+            c_int::from(gl_version.0),
+            osmesa_sys::OSMESA_CONTEXT_MINOR_VERSION,
+            c_int::from(gl_version.1),
+            0,
+        ];
+        let context =
+            unsafe { osmesa_sys::OSMesaCreateContextAttribs(attributes.as_ptr(), ptr::null_mut()) };
+
+        if context.is_null() {
+            return Err(format!(
+                "OSMesa can't create an OpenGL {}.{} core context",
+                gl_version.0, gl_version.1
+            ));
+        }
+
+        Ok(Self {
+            context,
+            buffer: vec![0; (width * height) as usize],
+            size: (width, height),
+        })
+    }
+
+    pub unsafe fn make_current(&mut self) -> Result<(), String> {
+        let result = osmesa_sys::OSMesaMakeCurrent(
+            self.context,
+            self.buffer.as_mut_ptr() as *mut c_void,
+            UNSIGNED_BYTE,
+            self.size.0 as c_int,
+            self.size.1 as c_int,
+        );
+
+        if result == 0 {
+            Err("Failed to make the OSMesa context current".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+
+        // NOTE: OSMesa returns a function pointer, gfx wants it untyped.
+        unsafe { mem::transmute(osmesa_sys::OSMesaGetProcAddress(symbol.as_ptr())) }
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        unsafe {
+            osmesa_sys::OSMesaDestroyContext(self.context);
+        }
+    }
+}
@@ -15,6 +15,8 @@ pub struct Input {
     mouse_left_pressed: bool,
     mouse_middle_pressed: bool,
     mouse_right_pressed: bool,
+    // NOTE: Logical size of the window, only set on the tick it was resized.
+    resized: Option<(f64, f64)>,
 }
 
 impl Input {
@@ -34,6 +36,7 @@ impl Input {
             mouse_left_pressed: false,
             mouse_middle_pressed: false,
             mouse_right_pressed: false,
+            resized: None,
         }
     }
 
@@ -66,6 +69,15 @@ impl Input {
         self.keys_up_this_tick.clear();
         self.mouse_axis = (0.0, 0.0);
         self.mouse_wheel = 0.0;
+        self.resized = None;
+    }
+
+    pub fn set_resized(&mut self, size: (f64, f64)) {
+        self.resized = Some(size);
+    }
+
+    pub fn get_resized(&self) -> Option<(f64, f64)> {
+        self.resized
     }
 
     pub fn set_mouse_position(&mut self, mouse_position: (f64, f64), screen_center: (f64, f64)) {
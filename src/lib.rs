@@ -19,19 +19,27 @@ pub mod events;
 pub use self::events::Events;
 
 pub mod renderer;
-pub use self::renderer::Renderer;
+pub use self::renderer::{Renderer, SceneTargets, Surface};
+
+#[cfg(target_os = "linux")]
+pub mod headless;
+#[cfg(target_os = "linux")]
+pub use self::headless::HeadlessContext;
+
+pub mod renderer_config;
+pub use self::renderer_config::{RendererConfig, WindowMode};
 
 pub mod pipeline;
 pub use self::pipeline::Pipeline;
 
 pub mod mesh;
-pub use self::mesh::Mesh;
+pub use self::mesh::{create_vertex_buffer_with_indices, needs_u32_indices, Mesh};
 
 pub mod camera;
 pub use self::camera::Camera;
 
 pub mod cube_builder;
-pub use self::cube_builder::cube_mesh_builder;
+pub use self::cube_builder::{cube_mesh_builder, BoxBuilder, BoxFace, FaceMask, BOX_FACES};
 
 pub type Resources = gfx_device_gl::Resources;
 pub type ColorFormat = gfx::format::Rgba8;
@@ -40,4 +48,51 @@ pub type DepthFormat = gfx::format::DepthStencil;
 pub mod voxel_mesh;
 pub use self::voxel_mesh::{Vertex, VoxelMesh, VoxelMeshPipe};
 
+pub mod frame;
+pub use self::frame::FrameLocals;
+
+pub mod fog;
+pub use self::fog::{Fog, FogMode};
+
+pub mod time_of_day;
+pub use self::time_of_day::TimeOfDay;
+
+pub mod shadow;
+pub use self::shadow::{ShadowCascades, ShadowCaster, ShadowMaps, ShadowPipe};
+
+pub mod post_processing;
+pub use self::post_processing::{PostEffect, PostProcessing};
+
+pub mod sky;
+pub use self::sky::{Sky, SkyColors, SkyPipe};
+
+pub mod chunk;
+pub use self::chunk::{
+    block_color, build_chunk_mesh, push_quad_indices, visit_faces, BlockId, Chunk, VisibleFace, AIR,
+    CHUNK_SIZE,
+};
+
+pub mod packed_voxel_mesh;
+pub use self::packed_voxel_mesh::{
+    build_packed_chunk_mesh, PackedVertex, PackedVoxelMesh, PackedVoxelMeshPipe, PALETTE_SIZE,
+};
+
+pub mod surface_nets;
+pub use self::surface_nets::{build_surface_nets_mesh, DensityField, MeshingMode};
+
+pub mod lod;
+pub use self::lod::{downsample_chunk, LodChunk, LodDistances, LOD_FACTORS};
+
+pub mod line_mesh;
+pub use self::line_mesh::{box_wireframe_vertices, LineMesh, LineMeshPipe, LineVertex};
+
+pub mod raycast;
+pub use self::raycast::{raycast_blocks, RaycastHit};
+
+pub mod debug_draw;
+pub use self::debug_draw::{sphere_wireframe_vertices, DebugDraw};
+
+pub mod selection;
+pub use self::selection::SelectionHighlight;
+
 pub mod gui;
@@ -0,0 +1,192 @@
+use super::gfx;
+use super::{Mesh, Pipeline, Renderer, Resources};
+use cgmath::Matrix4;
+use gfx::traits::FactoryExt;
+
+/// Fraction of the clip space depth lines are pulled towards the camera, so
+/// they win the depth test against the faces they lie on.
+pub const LINE_DEPTH_BIAS: f32 = 0.0005;
+
+gfx_defines! {
+    vertex LineVertex {
+        pos: [f32; 3] = "a_Pos",
+        color: [f32; 3] = "a_Color",
+    }
+
+    constant Locals {
+        model: [[f32; 4]; 4] = "u_Model",
+        view: [[f32; 4]; 4] = "u_View",
+        proj: [[f32; 4]; 4] = "u_Proj",
+        // x: depth bias
+        params: [f32; 4] = "u_Params",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<LineVertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = "Locals",
+        out: gfx::RawRenderTarget = (
+            "Target0",
+            gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Unorm),
+            gfx::state::ColorMask::all(),
+            None
+        ),
+        out_depth: gfx::DepthTarget<gfx::format::DepthStencil> =
+            gfx::preset::depth::LESS_EQUAL_TEST,
+    }
+}
+
+impl LineVertex {
+    pub fn new(pos: [f32; 3], color: [f32; 3]) -> Self {
+        Self { pos, color }
+    }
+}
+
+/// Twelve edges of the box from `min` to `max`, two vertices per edge.
+pub fn box_wireframe_vertices(min: [f32; 3], max: [f32; 3], color: [f32; 3]) -> Vec<LineVertex> {
+    let corner = |i: usize| {
+        [
+            if i & 1 == 0 { min[0] } else { max[0] },
+            if i & 2 == 0 { min[1] } else { max[1] },
+            if i & 4 == 0 { min[2] } else { max[2] },
+        ]
+    };
+    let mut vertices = Vec::with_capacity(24);
+
+    // Corners are indexed by their `zyx` bits, each edge flips one of them.
+    for i in 0..8 {
+        for &bit in &[1, 2, 4] {
+            if i & bit == 0 {
+                vertices.push(LineVertex::new(corner(i), color));
+                vertices.push(LineVertex::new(corner(i | bit), color));
+            }
+        }
+    }
+
+    vertices
+}
+
+pub struct LineMeshPipe {
+    pub pso: gfx::PipelineState<Resources, pipe::Meta>,
+}
+
+impl LineMeshPipe {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let vs_code = include_bytes!("shader/line_150_core.glslv").to_vec();
+        let fs_code = include_bytes!("shader/line_150_core.glslf").to_vec();
+
+        let init = pipe::Init {
+            out: (
+                "Target0",
+                renderer.scene.format,
+                gfx::state::ColorMask::all(),
+                None,
+            ),
+            ..pipe::new()
+        };
+
+        let shader_set = renderer
+            .factory
+            .create_shader_set(&vs_code, &fs_code)
+            .unwrap();
+        let pso = renderer
+            .factory
+            .create_pipeline_state(
+                &shader_set,
+                gfx::Primitive::LineList,
+                gfx::state::Rasterizer::new_fill(),
+                init,
+            )
+            .unwrap();
+
+        Self { pso }
+    }
+}
+
+impl Pipeline<pipe::Data<Resources>> for LineMeshPipe {
+    fn get_pso(&self) -> &gfx::PipelineState<Resources, pipe::Meta> {
+        &self.pso
+    }
+}
+
+/// Non-indexed line list, drawn with `LineMeshPipe`.
+#[derive(Clone, Debug)]
+pub struct LineMesh {
+    pub slice: gfx::Slice<Resources>,
+    pub data: pipe::Data<Resources>,
+    transform: Matrix4<f32>,
+}
+
+impl LineMesh {
+    pub fn new(renderer: &mut Renderer, vertices: &[LineVertex], transform: Matrix4<f32>) -> Self {
+        let (vbuf, slice) = renderer
+            .factory
+            .create_vertex_buffer_with_slice(vertices, ());
+
+        let data = pipe::Data {
+            vbuf,
+            locals: renderer.factory.create_constant_buffer(1),
+            out: renderer.scene.color.clone(),
+            out_depth: renderer.scene.depth_stencil.clone(),
+        };
+
+        Self {
+            slice,
+            data,
+            transform,
+        }
+    }
+
+    pub fn get_transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4<f32>) {
+        self.transform = transform;
+    }
+
+    pub fn update_locals(
+        &mut self,
+        renderer: &mut Renderer,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+    ) {
+        let locals = Locals {
+            model: self.transform.into(),
+            view: (*view).into(),
+            proj: (*proj).into(),
+            params: [LINE_DEPTH_BIAS, 0.0, 0.0, 0.0],
+        };
+
+        renderer
+            .encoder
+            .update_buffer(&self.data.locals, &[locals], 0)
+            .unwrap();
+        self.data.out = renderer.scene.color.clone();
+        self.data.out_depth = renderer.scene.depth_stencil.clone();
+    }
+}
+
+impl Mesh<pipe::Data<Resources>> for LineMesh {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+#[test]
+fn test_box_wireframe_vertices() {
+    let vertices = box_wireframe_vertices([0.0; 3], [1.0, 2.0, 3.0], [1.0; 3]);
+
+    assert_eq!(vertices.len(), 24);
+
+    // Every edge is parallel to one axis.
+    for edge in vertices.chunks(2) {
+        let changed_axes = (0..3)
+            .filter(|&axis| edge[0].pos[axis] != edge[1].pos[axis])
+            .count();
+        assert_eq!(changed_axes, 1);
+    }
+}
@@ -0,0 +1,216 @@
+use super::{
+    build_chunk_mesh, build_surface_nets_mesh, BlockId, Chunk, DensityField, MeshingMode, Renderer,
+    ShadowCaster, VoxelMesh, AIR,
+};
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector3};
+
+/// Downsampling factor of each level, level 0 being full resolution.
+pub const LOD_FACTORS: [usize; 4] = [1, 2, 4, 8];
+
+/// Camera distance from which each level after the first is used.
+#[derive(Clone, Debug)]
+pub struct LodDistances {
+    pub distances: [f32; 3],
+}
+
+impl Default for LodDistances {
+    fn default() -> Self {
+        Self {
+            distances: [64.0, 128.0, 256.0],
+        }
+    }
+}
+
+impl LodDistances {
+    pub fn select_level(&self, distance: f32) -> usize {
+        self.distances
+            .iter()
+            .take_while(|&&level_distance| distance >= level_distance)
+            .count()
+    }
+}
+
+/// Shrinks `chunk` by `factor`, each cell of the result taking the most
+/// common block of its `factor`³ source blocks. Cells less than half solid
+/// become air, so thin features vanish rather than bloat at a distance.
+pub fn downsample_chunk(chunk: &Chunk, factor: usize) -> Chunk {
+    let size = chunk.get_size() / factor;
+    let mut downsampled = Chunk::new(size);
+    let cell_volume = factor * factor * factor;
+    let mut counts: Vec<(BlockId, usize)> = Vec::with_capacity(cell_volume);
+
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                counts.clear();
+
+                for dz in 0..factor {
+                    for dy in 0..factor {
+                        for dx in 0..factor {
+                            let block =
+                                chunk.get(x * factor + dx, y * factor + dy, z * factor + dz);
+
+                            if block == AIR {
+                                continue;
+                            }
+
+                            match counts.iter_mut().find(|(counted, _)| *counted == block) {
+                                Some((_, count)) => *count += 1,
+                                None => counts.push((block, 1)),
+                            }
+                        }
+                    }
+                }
+
+                let solid_count: usize = counts.iter().map(|(_, count)| count).sum();
+
+                if solid_count * 2 < cell_volume {
+                    continue;
+                }
+
+                let (block, _) = counts
+                    .iter()
+                    .max_by_key(|(_, count)| *count)
+                    .cloned()
+                    .unwrap();
+
+                downsampled.set(x, y, z, block);
+            }
+        }
+    }
+
+    downsampled
+}
+
+/// GPU meshes of a chunk at every level of detail, built on first use.
+pub struct LodChunk {
+    chunk: Chunk,
+    position: Vector3<f32>,
+    meshing_mode: MeshingMode,
+    meshes: Vec<Option<VoxelMesh>>,
+    level: usize,
+    // Shadow caster of the mesh of the current level.
+    shadow_caster: Option<ShadowCaster>,
+}
+
+impl LodChunk {
+    pub fn new(chunk: Chunk, position: Vector3<f32>, meshing_mode: MeshingMode) -> Self {
+        Self {
+            chunk,
+            position,
+            meshing_mode,
+            meshes: LOD_FACTORS.iter().map(|_| None).collect(),
+            level: 0,
+            shadow_caster: None,
+        }
+    }
+
+    pub fn get_level(&self) -> usize {
+        self.level
+    }
+
+    pub fn get_chunk(&self) -> &Chunk {
+        &self.chunk
+    }
+
+    pub fn get_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    /// Whether the block at world coordinates `block` is solid in this chunk.
+    pub fn is_solid_at(&self, block: Point3<i32>) -> bool {
+        self.chunk.is_solid(
+            block.x as isize - self.position.x.floor() as isize,
+            block.y as isize - self.position.y.floor() as isize,
+            block.z as isize - self.position.z.floor() as isize,
+        )
+    }
+
+    pub fn get_center(&self) -> Point3<f32> {
+        let half_size = self.chunk.get_size() as f32 / 2.0;
+
+        Point3::from_vec(self.position + Vector3::new(half_size, half_size, half_size))
+    }
+
+    /// Picks the level for the current camera position, building its mesh on
+    /// first use and its shadow caster whenever the level changes.
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        camera_position: Point3<f32>,
+        lod_distances: &LodDistances,
+    ) {
+        let distance = self.get_center().distance(camera_position);
+        let level = lod_distances.select_level(distance);
+
+        if self.meshes[level].is_none() {
+            let factor = LOD_FACTORS[level];
+            let downsampled;
+            let chunk = if factor == 1 {
+                &self.chunk
+            } else {
+                downsampled = downsample_chunk(&self.chunk, factor);
+                &downsampled
+            };
+            let (vertices, indices) = match self.meshing_mode {
+                MeshingMode::Blocky => build_chunk_mesh(chunk, factor as f32, factor > 1),
+                MeshingMode::Smooth => {
+                    build_surface_nets_mesh(&DensityField::from_chunk(chunk), chunk, factor as f32)
+                }
+            };
+            let transform = Matrix4::from_translation(self.position);
+
+            self.meshes[level] = Some(VoxelMesh::new(renderer, &vertices, &indices, transform));
+        }
+
+        if self.shadow_caster.is_none() || level != self.level {
+            let mesh = self.meshes[level].as_ref().unwrap();
+
+            self.shadow_caster = Some(ShadowCaster::new(renderer, mesh));
+        }
+
+        self.level = level;
+    }
+
+    /// Mesh of the current level, to be called after `update`.
+    pub fn get_mesh_mut(&mut self) -> &mut VoxelMesh {
+        self.meshes[self.level].as_mut().unwrap()
+    }
+
+    /// Shadow caster of the current level, to be called after `update`.
+    pub fn get_shadow_caster_mut(&mut self) -> &mut ShadowCaster {
+        self.shadow_caster.as_mut().unwrap()
+    }
+}
+
+#[test]
+fn test_select_level() {
+    let lod_distances = LodDistances::default();
+
+    assert_eq!(lod_distances.select_level(0.0), 0);
+    assert_eq!(lod_distances.select_level(64.0), 1);
+    assert_eq!(lod_distances.select_level(200.0), 2);
+    assert_eq!(lod_distances.select_level(1000.0), 3);
+}
+
+#[test]
+fn test_downsample_chunk() {
+    let mut chunk = Chunk::new(4);
+
+    // Bottom half mostly block 3 with a few block 1, top half empty.
+    for z in 0..4 {
+        for y in 0..2 {
+            for x in 0..4 {
+                chunk.set(x, y, z, if x == 0 && y == 0 { 1 } else { 3 });
+            }
+        }
+    }
+
+    let downsampled = downsample_chunk(&chunk, 2);
+
+    assert_eq!(downsampled.get_size(), 2);
+    assert_eq!(downsampled.get(0, 0, 0), 3);
+    assert_eq!(downsampled.get(1, 0, 1), 3);
+    assert_eq!(downsampled.get(0, 1, 0), AIR);
+}
@@ -10,30 +10,77 @@ extern crate yoga;
 
 use cgmath::prelude::*;
 use cgmath::{Point3, Vector3};
-use terrain_generation::gui::{Element, Gui, UIMeshPipe, Arg};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+use terrain_generation::gui::{
+    Arg, DrawList, Element, ElementBuilder, EventContext, FontAtlas, Gui, MouseButton, State,
+    TextAlign, UIMeshPipe,
+};
 use terrain_generation::{
-    cube_mesh_builder, Camera, Events, Input, Lifecycle, LifecycleEvent, Renderer, VoxelMeshPipe,
+    cube_mesh_builder, raycast_blocks, Camera, Chunk, DebugDraw, Events, Fog, FrameLocals, Input,
+    Lifecycle, LifecycleEvent, LineMeshPipe, LodChunk, LodDistances, MeshingMode, PostEffect,
+    PostProcessing, Renderer, RendererConfig, SelectionHighlight, ShadowCascades, ShadowCaster,
+    ShadowPipe, Sky, SkyPipe, TimeOfDay, VoxelMeshPipe, CHUNK_SIZE,
 };
 use yoga::prelude::*;
-use yoga::FlexDirection;
+use yoga::{Align, FlexDirection};
+
+// Optional, the defaults are used without it.
+const RENDERER_CONFIG_PATH: &str = "renderer.cfg";
+// How far away blocks can be targeted.
+const REACH_DISTANCE: f32 = 64.0;
+const FONT_TTF: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+// Pixel size the font is rasterised at, text of other sizes is scaled from it.
+const FONT_ATLAS_SIZE: f32 = 32.0;
+// Half an hour per click on the time of day buttons.
+const TIME_SCRUB_STEP: f32 = 1.0 / 48.0;
+
+/// Requested by the debug UI, applied to the `TimeOfDay` once input is dispatched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TimeControl {
+    TogglePause,
+    Scrub(f32),
+}
+
+fn generate_chunk(chunk_x: i32, chunk_z: i32) -> Chunk {
+    let mut chunk = Chunk::new(CHUNK_SIZE);
+
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let world_x = (chunk_x * CHUNK_SIZE as i32 + x as i32) as f32;
+            let world_z = (chunk_z * CHUNK_SIZE as i32 + z as i32) as f32;
+            let height = 4.0 + 3.0 * (world_x * 0.15).sin() + 3.0 * (world_z * 0.1).cos();
+
+            for y in 0..(height as usize).min(CHUNK_SIZE) {
+                chunk.set(x, y, z, if y + 1 == height as usize { 1 } else { 2 });
+            }
+        }
+    }
+
+    chunk
+}
 
-fn hello<'a>(gui: &mut Gui) -> Element<'a> {
-    let color_state = gui.use_state([1.0, 0.0, 1.0]);
+fn hello(gui: &mut Gui) -> ElementBuilder {
+    let color_state = gui.use_state([1.0, 0.0, 1.0, 0.6]);
+    let enter_color_state = color_state.clone();
+    let leave_color_state = color_state.clone();
 
     Gui::create_element()
         .background_color(Arg::State(color_state))
+        .corner_radius(8.0)
         .style(&mut make_styles!(
             FlexDirection(FlexDirection::Row),
             Padding(10 pt)
         ))
-        .on_mouse_enter(&|| {
-            println!("on_mouse_enter");
-            // gui.set_state(color_state, [1.0, 0.3, 1.0]);
-            // color_state.set([1.0, 0.3, 1.0]);
-        })
+        .on_mouse_enter(move |_| enter_color_state.set([1.0, 0.3, 1.0, 0.8]))
+        .on_mouse_leave(move |_| leave_color_state.set([1.0, 0.0, 1.0, 0.6]))
         .child(
             Gui::create_element()
-                .background_color(Arg::Value([1.0, 1.0, 0.0]))
+                .background_color(Arg::Value([1.0, 1.0, 0.0, 1.0]))
+                .hover_background_color(Arg::Value([1.0, 0.6, 0.0, 1.0]))
+                .border_width(2.0)
+                .border_color(Arg::Value([0.4, 0.2, 0.0, 1.0]))
+                .corner_radius(6.0)
                 .style(&mut make_styles!(
                     Width(32 pt),
                     Height(32 pt),
@@ -42,13 +89,104 @@ fn hello<'a>(gui: &mut Gui) -> Element<'a> {
         )
         .child(
             Gui::create_element()
-                .background_color(Arg::Value([0.0, 1.0, 1.0]))
+                .background_color(Arg::Value([0.0, 1.0, 1.0, 1.0]))
                 .style(&mut make_styles!(
                     Width(32 pt),
                     Height(32 pt),
                     FlexGrow(0.0)
                 )),
         )
+        .child(
+            gui.create_text("Hello")
+                .font_size(24.0)
+                .color(Arg::Value([1.0, 1.0, 1.0, 1.0]))
+                .text_align(TextAlign::Center)
+                .style(&mut make_styles!(FlexGrow(1.0))),
+        )
+}
+
+fn button<F: FnMut(&mut EventContext) + 'static>(
+    gui: &Gui,
+    label: &str,
+    on_click: F,
+) -> ElementBuilder {
+    Gui::create_element()
+        .background_color(Arg::Value([0.2, 0.2, 0.2, 0.8]))
+        .hover_background_color(Arg::Value([0.35, 0.35, 0.35, 0.8]))
+        .corner_radius(4.0)
+        .style(&mut make_styles!(Padding(4 pt), Margin(2 pt)))
+        .on_click(on_click)
+        .child(
+            gui.create_text(label)
+                .font_size(16.0)
+                .color(Arg::Value([1.0, 1.0, 1.0, 1.0])),
+        )
+}
+
+fn time_controls(
+    gui: &Gui,
+    time_of_day: &TimeOfDay,
+    time_control: &State<Option<TimeControl>>,
+) -> ElementBuilder {
+    let request = |control: TimeControl| {
+        let time_control = time_control.clone();
+        move |_: &mut EventContext| time_control.set(Some(control))
+    };
+    let hours = time_of_day.get_time() * 24.0;
+    let clock = format!("{:02}:{:02}", hours as u32, (hours.fract() * 60.0) as u32);
+    let pause_label = if time_of_day.is_paused() {
+        "Play"
+    } else {
+        "Pause"
+    };
+
+    Gui::create_element()
+        .background_color(Arg::Value([0.0, 0.0, 0.0, 0.5]))
+        .corner_radius(4.0)
+        .style(&mut make_styles!(
+            FlexDirection(FlexDirection::Row),
+            AlignItems(Align::Center),
+            AlignSelf(Align::FlexStart),
+            Padding(4 pt),
+            Margin(10 pt)
+        ))
+        .child(button(
+            gui,
+            "<",
+            request(TimeControl::Scrub(-TIME_SCRUB_STEP)),
+        ))
+        .child(button(gui, pause_label, request(TimeControl::TogglePause)))
+        .child(button(
+            gui,
+            ">",
+            request(TimeControl::Scrub(TIME_SCRUB_STEP)),
+        ))
+        .child(
+            gui.create_text(&clock)
+                .font_size(16.0)
+                .color(Arg::Value([1.0, 1.0, 1.0, 1.0]))
+                .style(&mut make_styles!(Margin(4 pt))),
+        )
+}
+
+fn debug_ui(
+    gui: &mut Gui,
+    time_of_day: &TimeOfDay,
+    time_control: &State<Option<TimeControl>>,
+    draw_calls: usize,
+) -> Element {
+    let hello = hello(gui);
+
+    Gui::create_element()
+        .background_color(Arg::Value([0.0; 4]))
+        .child(hello)
+        .child(time_controls(gui, time_of_day, time_control))
+        .child(
+            gui.create_text(&format!("Draw calls: {}", draw_calls))
+                .font_size(16.0)
+                .color(Arg::Value([1.0, 1.0, 1.0, 1.0]))
+                .style(&mut make_styles!(Margin(10 pt))),
+        )
         .build()
 }
 
@@ -56,11 +194,35 @@ pub fn main() {
     let mut input = Input::new();
     let mut events = Events::new();
     let mut lifecycle = Lifecycle::new();
-    let mut renderer = Renderer::new(&mut events);
+    let renderer_config = match RendererConfig::from_file(RENDERER_CONFIG_PATH) {
+        Ok(renderer_config) => renderer_config,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => RendererConfig::default(),
+        Err(error) => {
+            println!("Using default renderer config: {}", error);
+            RendererConfig::default()
+        }
+    };
+    let mut renderer = Renderer::with_config(&mut events, &renderer_config);
     let mut gui = Gui::new();
+    gui.set_font(FontAtlas::from_ttf(FONT_TTF.to_vec(), FONT_ATLAS_SIZE).expect("Invalid font"));
+    let window_size = renderer.get_size();
+    gui.resize(
+        window_size.width as f32,
+        window_size.height as f32,
+        renderer.get_hidpi_factor() as f32,
+    );
+    let mut gui_draw_list = DrawList::new();
 
     let pipe = VoxelMeshPipe::new(&mut renderer);
     let ui_pipe = UIMeshPipe::new(&mut renderer);
+    let sky_pipe = SkyPipe::new(&mut renderer);
+    let shadow_pipe = ShadowPipe::new(&mut renderer);
+    let line_pipe = LineMeshPipe::new(&mut renderer);
+    let mut post_processing = if renderer.scene.is_offscreen() {
+        Some(PostProcessing::new(&mut renderer))
+    } else {
+        None
+    };
 
     let camera = Camera::new(
         &renderer,
@@ -68,29 +230,210 @@ pub fn main() {
         (Point3::new(0.0, 0.0, 0.0) - Point3::new(0.0, 2.0, 5.0)).normalize(),
     );
 
+    let mut sky = Sky::new(&mut renderer);
+    let mut time_of_day = TimeOfDay::default();
+    let time_control = State::new(None);
+    let mut fog = Fog::default();
+    let mut frame_locals = FrameLocals::default();
+
     let mut mesh1 = cube_mesh_builder(&mut renderer, Vector3::new(0.0, 0.0, 0.0), [1.0, 0.2, 0.3]);
     let mut mesh2 = cube_mesh_builder(&mut renderer, Vector3::new(0.0, 0.0, -5.0), [0.2, 1.0, 0.3]);
+    let lod_distances = LodDistances::default();
+    // NOTE: Block selection and LOD skirts only match blocky meshes.
+    let meshing_mode = MeshingMode::Blocky;
+    let mut terrain: Vec<LodChunk> = Vec::new();
+    for chunk_z in -4..4 {
+        for chunk_x in -4..4 {
+            let position = Vector3::new(
+                (chunk_x * CHUNK_SIZE as i32) as f32,
+                -10.0,
+                (chunk_z * CHUNK_SIZE as i32) as f32,
+            );
+            terrain.push(LodChunk::new(
+                generate_chunk(chunk_x, chunk_z),
+                position,
+                meshing_mode,
+            ));
+        }
+    }
+
+    let mut selection = SelectionHighlight::new(&mut renderer);
+    let mut debug_draw = DebugDraw::new(&mut renderer);
+
+    let mut shadow_caster1 = ShadowCaster::new(&mut renderer, &mesh1);
+    let mut shadow_caster2 = ShadowCaster::new(&mut renderer, &mesh2);
+    let mut shadow_cascades = ShadowCascades::new(150.0);
 
     while let Some(event) = lifecycle.next() {
         match event {
-            LifecycleEvent::FixedUpdate(_fixed_delta_time) => {}
+            LifecycleEvent::FixedUpdate(fixed_delta_time) => {
+                time_of_day.fixed_update(fixed_delta_time);
+            }
             LifecycleEvent::Update(_delta_time) => {
+                input.new_tick();
                 events.update(&mut renderer, &mut input);
 
+                if input.get_key_down(glutin::VirtualKeyCode::F11) {
+                    renderer.toggle_fullscreen();
+                }
+
+                if input.get_key_down(glutin::VirtualKeyCode::F2) {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+                    renderer.capture_screenshot(format!("screenshot-{}.png", timestamp));
+                }
+
+                if let Some((width, height)) = input.get_resized() {
+                    gui.resize(
+                        width as f32,
+                        height as f32,
+                        renderer.get_hidpi_factor() as f32,
+                    );
+                }
+
                 let mouse_position = input.get_mouse_position();
                 gui.set_mouse_position(mouse_position.0 as f32, mouse_position.1 as f32);
+                gui.set_mouse_button(MouseButton::Left, input.get_mouse_left());
+                gui.set_mouse_button(MouseButton::Middle, input.get_mouse_middle());
+                gui.set_mouse_button(MouseButton::Right, input.get_mouse_right());
+
+                gui.new_frame();
+                // NOTE: Draw calls are counted from `clear`, this is the count of the last frame.
+                let draw_calls = renderer.get_draw_calls();
+                let mut ui = debug_ui(&mut gui, &time_of_day, &time_control, draw_calls);
+                gui.layout(&mut ui);
+                gui.dispatch_input(&mut ui);
+                gui_draw_list.clear();
+                gui.build_draw_list(&ui, &mut gui_draw_list);
+
+                match time_control.get() {
+                    Some(TimeControl::TogglePause) => time_of_day.toggle_pause(),
+                    Some(TimeControl::Scrub(delta)) => {
+                        let time = time_of_day.get_time();
+                        time_of_day.set_time(time + delta);
+                    }
+                    None => {}
+                }
+                time_control.set(None);
+
+                if let Some(ref mut post_processing) = post_processing {
+                    let effect_keys = [
+                        (glutin::VirtualKeyCode::F5, PostEffect::Tonemapping),
+                        (glutin::VirtualKeyCode::F6, PostEffect::Gamma),
+                        (glutin::VirtualKeyCode::F7, PostEffect::Fxaa),
+                        (glutin::VirtualKeyCode::F8, PostEffect::Vignette),
+                    ];
+
+                    for &(key, effect) in &effect_keys {
+                        if input.get_key_down(key) {
+                            post_processing.toggle(effect);
+                        }
+                    }
+                }
+
+                if input.get_key_down(glutin::VirtualKeyCode::F3) {
+                    debug_draw.toggle();
+                }
+                if input.get_key_down(glutin::VirtualKeyCode::P) {
+                    time_of_day.toggle_pause();
+                }
+                if input.get_key(glutin::VirtualKeyCode::LBracket) {
+                    let time = time_of_day.get_time();
+                    time_of_day.set_time(time - 0.002);
+                }
+                if input.get_key(glutin::VirtualKeyCode::RBracket) {
+                    let time = time_of_day.get_time();
+                    time_of_day.set_time(time + 0.002);
+                }
 
+                let horizon = time_of_day.get_sky_colors().horizon;
+                renderer.set_clear_color([horizon[0], horizon[1], horizon[2], 1.0]);
+                fog.color = horizon;
+                time_of_day.update_frame_locals(&mut frame_locals);
+                fog.update_frame_locals(&mut frame_locals);
+                shadow_cascades.update(&camera, time_of_day.get_sun_direction());
+                shadow_cascades.update_frame_locals(&mut frame_locals);
+                renderer.update_frame_locals(&frame_locals);
+                sky.update_locals(
+                    &mut renderer,
+                    &camera.get_view(),
+                    camera.get_projection(),
+                    &time_of_day,
+                );
                 mesh1.update_locals(&mut renderer, &camera.get_view(), camera.get_projection());
                 mesh2.update_locals(&mut renderer, &camera.get_view(), camera.get_projection());
 
-                let hello = hello(&mut gui);
+                let hit = raycast_blocks(
+                    camera.get_position(),
+                    camera.get_forward(),
+                    REACH_DISTANCE,
+                    |block| terrain.iter().any(|lod_chunk| lod_chunk.is_solid_at(block)),
+                );
+                selection.set_target(hit.map(|hit| hit.block));
+
+                debug_draw.axes(Point3::new(0.0, 0.0, 0.0), 2.0);
+                for lod_chunk in &terrain {
+                    let min = Point3::from_vec(lod_chunk.get_position());
+                    let size = lod_chunk.get_chunk().get_size() as f32;
+                    debug_draw.aabb(min, min + Vector3::new(size, size, size), [1.0, 1.0, 0.0]);
+                }
+                if let Some(hit) = hit {
+                    debug_draw.ray(
+                        camera.get_position(),
+                        camera.get_forward(),
+                        hit.distance,
+                        [1.0, 0.0, 0.0],
+                    );
+                }
 
                 renderer.clear();
+                shadow_cascades.clear(&mut renderer);
+                shadow_cascades.draw(&mut renderer, &mut shadow_caster1, &shadow_pipe);
+                shadow_cascades.draw(&mut renderer, &mut shadow_caster2, &shadow_pipe);
+                for lod_chunk in &mut terrain {
+                    lod_chunk.update(&mut renderer, camera.get_position(), &lod_distances);
+                    shadow_cascades.draw(
+                        &mut renderer,
+                        lod_chunk.get_shadow_caster_mut(),
+                        &shadow_pipe,
+                    );
+                }
+                renderer.draw(&mut sky, &sky_pipe);
                 renderer.draw(&mut mesh1, &pipe);
                 renderer.draw(&mut mesh2, &pipe);
-                gui.render(&mut renderer, &ui_pipe, hello);
+                for lod_chunk in &mut terrain {
+                    let mesh = lod_chunk.get_mesh_mut();
+                    mesh.update_locals(&mut renderer, &camera.get_view(), camera.get_projection());
+                    renderer.draw(mesh, &pipe);
+                }
+                selection.draw(
+                    &mut renderer,
+                    &line_pipe,
+                    &camera.get_view(),
+                    camera.get_projection(),
+                );
+                debug_draw.render(
+                    &mut renderer,
+                    &line_pipe,
+                    &camera.get_view(),
+                    camera.get_projection(),
+                );
+                if let Some(ref mut post_processing) = post_processing {
+                    post_processing.render(&mut renderer);
+                }
+                gui.render(&mut renderer, &ui_pipe, &gui_draw_list);
                 renderer.flush();
 
+                match renderer.take_screenshot_result() {
+                    Some((path, Ok(()))) => println!("Screenshot saved to {}", path.display()),
+                    Some((path, Err(error))) => {
+                        println!("Failed to save screenshot {}: {}", path.display(), error)
+                    }
+                    None => {}
+                }
+
                 if !events.is_running() {
                     return;
                 }
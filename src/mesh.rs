@@ -1,7 +1,76 @@
 use super::gfx;
 use super::Resources;
+use gfx::traits::FactoryExt;
 
 pub trait Mesh<PD: gfx::pso::PipelineData<Resources>> {
     fn get_data(&self) -> &PD;
     fn get_slice(&self) -> &gfx::Slice<Resources>;
 }
+
+/// Whether some of `vertex_count` vertices can't be addressed with 16-bit indices.
+pub fn needs_u32_indices(vertex_count: usize) -> bool {
+    vertex_count > usize::from(u16::max_value()) + 1
+}
+
+/// `indices` narrowed to 16 bits, or `None` when they must stay 32 bits wide
+/// to address `vertex_count` vertices.
+fn narrow_indices(vertex_count: usize, indices: &[u32]) -> Option<Vec<u16>> {
+    if needs_u32_indices(vertex_count) {
+        None
+    } else {
+        Some(indices.iter().map(|&index| index as u16).collect())
+    }
+}
+
+/// Uploads `vertices` with a slice drawing them through `indices`, which are
+/// narrowed to 16 bits whenever every vertex can be addressed with them.
+pub fn create_vertex_buffer_with_indices<V>(
+    factory: &mut gfx_device_gl::Factory,
+    vertices: &[V],
+    indices: &[u32],
+) -> (gfx::handle::Buffer<Resources, V>, gfx::Slice<Resources>)
+where
+    V: gfx::traits::Pod + gfx::pso::buffer::Structure<gfx::format::Format>,
+{
+    match narrow_indices(vertices.len(), indices) {
+        Some(indices) => factory.create_vertex_buffer_with_slice(vertices, &indices[..]),
+        None => factory.create_vertex_buffer_with_slice(vertices, indices),
+    }
+}
+
+#[test]
+fn test_needs_u32_indices() {
+    assert!(!needs_u32_indices(65535));
+    // Indices 0 to 65535 still fit in 16 bits.
+    assert!(!needs_u32_indices(65536));
+    assert!(needs_u32_indices(65537));
+}
+
+#[test]
+fn test_large_mesh_keeps_u32_indices() {
+    use super::{build_chunk_mesh, Chunk};
+
+    // Every other block solid, so no face is culled.
+    let mut chunk = Chunk::new(24);
+    for z in 0..24 {
+        for y in 0..24 {
+            for x in 0..24 {
+                if (x + y + z) % 2 == 0 {
+                    chunk.set(x, y, z, 1);
+                }
+            }
+        }
+    }
+
+    let (vertices, indices) = build_chunk_mesh(&chunk, 1.0, false);
+
+    assert!(needs_u32_indices(vertices.len()));
+    assert_eq!(narrow_indices(vertices.len(), &indices), None);
+    assert_eq!(indices.iter().max(), Some(&(vertices.len() as u32 - 1)));
+
+    let small_indices = [0, 1, 65535];
+    assert_eq!(
+        narrow_indices(65536, &small_indices),
+        Some(vec![0, 1, 65535])
+    );
+}
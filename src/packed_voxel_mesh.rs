@@ -0,0 +1,293 @@
+use super::gfx;
+use super::{
+    block_color, create_vertex_buffer_with_indices, push_quad_indices, visit_faces, BlockId, Chunk,
+    FrameLocals, Mesh, Pipeline, Renderer, Resources,
+};
+use cgmath::Matrix4;
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+
+/// Number of block colors uploaded to the packed vertex shader, higher block
+/// IDs use the last one.
+pub const PALETTE_SIZE: usize = 16;
+
+gfx_defines! {
+    // NOTE: 8 bytes per vertex instead of 36 for `Vertex`.
+    //   data[0]: signed x, y and z in blocks, normal index in bits 24-26, AO in bits 27-28
+    //   data[1]: block ID in bits 0-7, the rest is free for a texture ID
+    vertex PackedVertex {
+        data: [u32; 2] = "a_Data",
+    }
+
+    constant Locals {
+        model: [[f32; 4]; 4] = "u_Model",
+        view: [[f32; 4]; 4] = "u_View",
+        proj: [[f32; 4]; 4] = "u_Proj",
+    }
+
+    constant PaletteColor {
+        color: [f32; 4] = "color",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<PackedVertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = "Locals",
+        palette: gfx::ConstantBuffer<PaletteColor> = "Palette",
+        frame_locals: gfx::ConstantBuffer<FrameLocals> = "FrameLocals",
+        shadow_map: gfx::TextureSampler<f32> = "t_ShadowMap",
+        out: gfx::RawRenderTarget = (
+            "Target0",
+            gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Unorm),
+            gfx::state::ColorMask::all(),
+            None
+        ),
+        out_depth: gfx::DepthTarget<gfx::format::DepthStencil> =
+            gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
+}
+
+impl PackedVertex {
+    pub fn new(p: [i8; 3], normal_index: u8, ambient_occlusion: u8, block: BlockId) -> Self {
+        let position = u32::from(p[0] as u8)
+            | u32::from(p[1] as u8) << 8
+            | u32::from(p[2] as u8) << 16;
+
+        Self {
+            data: [
+                position
+                    | u32::from(normal_index & 0x7) << 24
+                    | u32::from(ambient_occlusion & 0x3) << 27,
+                u32::from(block),
+            ],
+        }
+    }
+
+    pub fn get_position(&self) -> [i8; 3] {
+        [
+            self.data[0] as u8 as i8,
+            (self.data[0] >> 8) as u8 as i8,
+            (self.data[0] >> 16) as u8 as i8,
+        ]
+    }
+
+    pub fn get_normal_index(&self) -> u8 {
+        (self.data[0] >> 24) as u8 & 0x7
+    }
+
+    pub fn get_ambient_occlusion(&self) -> u8 {
+        (self.data[0] >> 27) as u8 & 0x3
+    }
+
+    pub fn get_block(&self) -> BlockId {
+        self.data[1] as BlockId
+    }
+}
+
+/// Builds the visible faces of `chunk` as packed vertices, one unit per
+/// block; scale the mesh through its transform for lower levels of detail.
+///
+/// Panics when `chunk` is too large for the signed 8-bit packed positions.
+pub fn build_packed_chunk_mesh(chunk: &Chunk, skirts: bool) -> (Vec<PackedVertex>, Vec<u32>) {
+    assert!(
+        chunk.get_size() <= i8::max_value() as usize,
+        "Packed chunk meshes are at most 127 blocks wide"
+    );
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    visit_faces(chunk, skirts, |face| {
+        push_quad_indices(&mut indices, vertices.len() as u32);
+
+        for (corner, &ambient_occlusion) in face.corners.iter().zip(&face.ambient_occlusion) {
+            vertices.push(PackedVertex::new(
+                [corner[0] as i8, corner[1] as i8, corner[2] as i8],
+                face.normal_index,
+                ambient_occlusion,
+                face.block,
+            ));
+        }
+    });
+
+    (vertices, indices)
+}
+
+pub struct PackedVoxelMeshPipe {
+    pub pso: gfx::PipelineState<Resources, pipe::Meta>,
+}
+
+impl PackedVoxelMeshPipe {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let (vs_code, fs_code) = if cfg!(target_os = "emscripten") {
+            (
+                include_bytes!("shader/packed_300_es.glslv").to_vec(),
+                include_bytes!("shader/triangle_300_es.glslf").to_vec(),
+            )
+        } else {
+            (
+                include_bytes!("shader/packed_150_core.glslv").to_vec(),
+                include_bytes!("shader/triangle_150_core.glslf").to_vec(),
+            )
+        };
+
+        let init = pipe::Init {
+            out: (
+                "Target0",
+                renderer.scene.format,
+                gfx::state::ColorMask::all(),
+                None,
+            ),
+            ..pipe::new()
+        };
+
+        let pso = renderer
+            .factory
+            .create_pipeline_simple(&vs_code, &fs_code, init)
+            .unwrap();
+
+        Self { pso }
+    }
+}
+
+impl Pipeline<pipe::Data<Resources>> for PackedVoxelMeshPipe {
+    fn get_pso(&self) -> &gfx::PipelineState<Resources, pipe::Meta> {
+        &self.pso
+    }
+}
+
+/// Voxel mesh made of `PackedVertex`, drawn with `PackedVoxelMeshPipe`.
+#[derive(Clone, Debug)]
+pub struct PackedVoxelMesh {
+    pub slice: gfx::Slice<Resources>,
+    pub data: pipe::Data<Resources>,
+    transform: Matrix4<f32>,
+}
+
+impl PackedVoxelMesh {
+    pub fn new(
+        renderer: &mut Renderer,
+        vertices: &[PackedVertex],
+        indices: &[u32],
+        transform: Matrix4<f32>,
+    ) -> Self {
+        let (vbuf, slice) =
+            create_vertex_buffer_with_indices(&mut renderer.factory, vertices, indices);
+
+        let palette_colors: Vec<PaletteColor> = (0..PALETTE_SIZE)
+            .map(|block| {
+                let [r, g, b] = block_color(block as BlockId);
+                PaletteColor {
+                    color: [r, g, b, 1.0],
+                }
+            })
+            .collect();
+        let palette = renderer
+            .factory
+            .create_buffer_immutable(
+                &palette_colors,
+                gfx::buffer::Role::Constant,
+                gfx::memory::Bind::empty(),
+            )
+            .unwrap();
+
+        let data = pipe::Data {
+            vbuf,
+            locals: renderer.factory.create_constant_buffer(1),
+            palette,
+            frame_locals: renderer.frame_locals.clone(),
+            shadow_map: (
+                renderer.shadow_maps.resource.clone(),
+                renderer.shadow_maps.sampler.clone(),
+            ),
+            out: renderer.scene.color.clone(),
+            out_depth: renderer.scene.depth_stencil.clone(),
+        };
+
+        Self {
+            data,
+            slice,
+            transform,
+        }
+    }
+
+    pub fn get_transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
+    pub fn update_locals(
+        &mut self,
+        renderer: &mut Renderer,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+    ) {
+        let locals = Locals {
+            model: self.transform.into(),
+            view: (*view).into(),
+            proj: (*proj).into(),
+        };
+
+        renderer
+            .encoder
+            .update_buffer(&self.data.locals, &[locals], 0)
+            .unwrap();
+        self.data.out = renderer.scene.color.clone();
+        self.data.out_depth = renderer.scene.depth_stencil.clone();
+    }
+}
+
+impl Mesh<pipe::Data<Resources>> for PackedVoxelMesh {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+#[test]
+fn test_packed_vertex_round_trip() {
+    let vertex = PackedVertex::new([15, -1, 7], 4, 2, 3);
+
+    assert_eq!(vertex.get_position(), [15, -1, 7]);
+    assert_eq!(vertex.get_normal_index(), 4);
+    assert_eq!(vertex.get_ambient_occlusion(), 2);
+    assert_eq!(vertex.get_block(), 3);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_render_packed_chunk() {
+    use super::{Camera, RendererConfig};
+    use cgmath::{Point3, SquareMatrix, Vector3};
+
+    let mut renderer = Renderer::new_headless(&RendererConfig::new().with_size(16, 16));
+    let pipe = PackedVoxelMeshPipe::new(&mut renderer);
+    let mut chunk = Chunk::new(4);
+    chunk.set(0, 0, 0, 1);
+    let (vertices, indices) = build_packed_chunk_mesh(&chunk, false);
+    let mut mesh = PackedVoxelMesh::new(&mut renderer, &vertices, &indices, Matrix4::identity());
+    let camera = Camera::new(
+        &renderer,
+        Point3::new(0.5, 0.5, 3.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    );
+
+    renderer.set_clear_color([0.0, 0.0, 0.0, 1.0]);
+    renderer.update_frame_locals(&FrameLocals::default());
+    mesh.update_locals(&mut renderer, &camera.get_view(), camera.get_projection());
+    renderer.clear();
+    renderer.draw(&mut mesh, &pipe);
+    renderer.flush();
+
+    // The block is in the middle of the frame, lit with the palette color of grass.
+    let pixels = renderer.read_pixels();
+    let pixel = |x: usize, y: usize| &pixels[(y * 16 + x) * 4..(y * 16 + x + 1) * 4];
+    assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+    let center = pixel(8, 8);
+    assert!(
+        center[1] > center[0] && center[1] > center[2],
+        "{:?}",
+        center
+    );
+}
@@ -0,0 +1,347 @@
+use super::gfx;
+use super::renderer::HdrColorFormat;
+use super::{ColorFormat, Mesh, Pipeline, Renderer, Resources};
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+
+gfx_defines! {
+    vertex Vertex {
+        pos: [f32; 2] = "a_Pos",
+    }
+
+    constant Locals {
+        // 1 / width, 1 / height, width, height of the source.
+        texel_size: [f32; 4] = "u_TexelSize",
+        // Effect specific parameters.
+        params: [f32; 4] = "u_Params",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = "Locals",
+        source: gfx::TextureSampler<[f32; 4]> = "t_Source",
+        out: gfx::RenderTarget<ColorFormat> = "Target0",
+    }
+}
+
+type SourceView = gfx::handle::ShaderResourceView<Resources, [f32; 4]>;
+type TargetView = gfx::handle::RenderTargetView<Resources, ColorFormat>;
+
+/// Effects in the order they are applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostEffect {
+    Tonemapping,
+    Gamma,
+    Fxaa,
+    Vignette,
+}
+
+const EFFECTS: [PostEffect; 4] = [
+    PostEffect::Tonemapping,
+    PostEffect::Gamma,
+    PostEffect::Fxaa,
+    PostEffect::Vignette,
+];
+
+/// Effects of the passes to run for `enabled`, in the order of `EFFECTS`
+/// whatever order they were enabled in. `None` stands for the copy pass, run
+/// alone when no effect is enabled so the scene still reaches the window.
+fn pass_chain(enabled: &[PostEffect]) -> Vec<Option<PostEffect>> {
+    let chain: Vec<_> = EFFECTS
+        .iter()
+        .filter(|effect| enabled.contains(effect))
+        .map(|&effect| Some(effect))
+        .collect();
+
+    if chain.is_empty() {
+        vec![None]
+    } else {
+        chain
+    }
+}
+
+/// A full-screen pass reading one texture and writing one color target.
+pub struct PostPass {
+    pub pso: gfx::PipelineState<Resources, pipe::Meta>,
+}
+
+impl PostPass {
+    pub fn new(renderer: &mut Renderer, fs_code: &[u8]) -> Self {
+        let vs_code = include_bytes!("shader/post_150_core.glslv");
+
+        let pso = renderer
+            .factory
+            .create_pipeline_simple(vs_code, fs_code, pipe::new())
+            .unwrap();
+
+        Self { pso }
+    }
+}
+
+impl Pipeline<pipe::Data<Resources>> for PostPass {
+    fn get_pso(&self) -> &gfx::PipelineState<Resources, pipe::Meta> {
+        &self.pso
+    }
+}
+
+struct FullScreenQuad {
+    slice: gfx::Slice<Resources>,
+    data: pipe::Data<Resources>,
+}
+
+impl Mesh<pipe::Data<Resources>> for FullScreenQuad {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+/// Chain of full-screen passes turning the offscreen scene into the final
+/// image in the window. Requires `RendererConfig::post_processing`.
+pub struct PostProcessing {
+    pub exposure: f32,
+    pub gamma: f32,
+    pub vignette_strength: f32,
+    pub vignette_radius: f32,
+    enabled: Vec<PostEffect>,
+    copy_pass: PostPass,
+    passes: Vec<(PostEffect, PostPass)>,
+    quad: FullScreenQuad,
+    // Ping-pong targets for intermediate passes.
+    buffers: [(SourceView, TargetView); 2],
+    // NOTE: The scene and intermediate targets all share this size, the
+    // intermediate ones are created again when the scene is resized.
+    size: (u16, u16),
+}
+
+impl PostProcessing {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let scene_resource = renderer
+            .scene
+            .resource
+            .clone()
+            .expect("Post-processing requires the scene to be rendered offscreen");
+        let (width, height, _, _) = renderer.scene.color.get_dimensions();
+
+        let copy_pass = PostPass::new(renderer, include_bytes!("shader/post_copy_150_core.glslf"));
+        let passes = EFFECTS
+            .iter()
+            .map(|&effect| {
+                let fs_code: &[u8] = match effect {
+                    PostEffect::Tonemapping => include_bytes!("shader/post_tonemap_150_core.glslf"),
+                    PostEffect::Gamma => include_bytes!("shader/post_gamma_150_core.glslf"),
+                    PostEffect::Fxaa => include_bytes!("shader/post_fxaa_150_core.glslf"),
+                    PostEffect::Vignette => include_bytes!("shader/post_vignette_150_core.glslf"),
+                };
+
+                (effect, PostPass::new(renderer, fs_code))
+            })
+            .collect();
+
+        let vertices: &[Vertex] = &[
+            Vertex { pos: [-1.0, -1.0] },
+            Vertex { pos: [1.0, -1.0] },
+            Vertex { pos: [1.0, 1.0] },
+            Vertex { pos: [-1.0, 1.0] },
+        ];
+
+        const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
+
+        let (vbuf, slice) = renderer
+            .factory
+            .create_vertex_buffer_with_slice(vertices, INDICES);
+        let sampler = renderer.factory.create_sampler_linear();
+
+        let quad = FullScreenQuad {
+            slice,
+            data: pipe::Data {
+                vbuf,
+                locals: renderer.factory.create_constant_buffer(1),
+                source: (scene_resource, sampler),
+                out: renderer.render_target.clone(),
+            },
+        };
+
+        let buffers = Self::create_buffers(renderer, (width, height));
+
+        // NOTE: Tonemapping would darken an LDR scene, already in [0, 1].
+        let is_hdr =
+            renderer.scene.format == <HdrColorFormat as gfx::format::Formatted>::get_format();
+        let enabled = if is_hdr {
+            vec![PostEffect::Tonemapping, PostEffect::Fxaa]
+        } else {
+            vec![PostEffect::Fxaa]
+        };
+
+        Self {
+            exposure: 1.0,
+            gamma: 2.2,
+            vignette_strength: 0.4,
+            vignette_radius: 0.75,
+            enabled,
+            copy_pass,
+            passes,
+            quad,
+            buffers,
+            size: (width, height),
+        }
+    }
+
+    fn create_buffers(renderer: &mut Renderer, size: (u16, u16)) -> [(SourceView, TargetView); 2] {
+        let mut create_buffer = || {
+            let (_, resource, target) = renderer
+                .factory
+                .create_render_target::<ColorFormat>(size.0, size.1)
+                .unwrap();
+
+            (resource, target)
+        };
+
+        [create_buffer(), create_buffer()]
+    }
+
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        self.enabled.contains(&effect)
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, enabled: bool) {
+        self.enabled.retain(|&enabled_effect| enabled_effect != effect);
+
+        if enabled {
+            self.enabled.push(effect);
+        }
+    }
+
+    pub fn toggle(&mut self, effect: PostEffect) {
+        let enabled = self.is_enabled(effect);
+
+        self.set_enabled(effect, !enabled);
+    }
+
+    fn get_params(&self, effect: PostEffect) -> [f32; 4] {
+        match effect {
+            PostEffect::Tonemapping => [self.exposure, 0.0, 0.0, 0.0],
+            PostEffect::Gamma => [self.gamma, 0.0, 0.0, 0.0],
+            PostEffect::Fxaa => [0.0, 0.0, 0.0, 0.0],
+            PostEffect::Vignette => [self.vignette_strength, self.vignette_radius, 0.0, 0.0],
+        }
+    }
+
+    /// Runs the enabled passes on the scene, the last one writing to the window.
+    pub fn render(&mut self, renderer: &mut Renderer) {
+        let scene_resource = match renderer.scene.resource {
+            Some(ref resource) => resource.clone(),
+            None => return,
+        };
+        let (width, height, _, _) = renderer.scene.color.get_dimensions();
+
+        if (width, height) != self.size {
+            self.buffers = Self::create_buffers(renderer, (width, height));
+            self.size = (width, height);
+        }
+
+        let chain = pass_chain(&self.enabled);
+        let mut source = scene_resource;
+
+        for (index, &effect) in chain.iter().enumerate() {
+            let (params, pass) = match effect {
+                Some(effect) => {
+                    let (_, pass) = self
+                        .passes
+                        .iter()
+                        .find(|(pass_effect, _)| *pass_effect == effect)
+                        .unwrap();
+
+                    (self.get_params(effect), pass)
+                }
+                None => ([0.0; 4], &self.copy_pass),
+            };
+            let target = if index == chain.len() - 1 {
+                renderer.render_target.clone()
+            } else {
+                self.buffers[index % 2].1.clone()
+            };
+
+            let locals = Locals {
+                texel_size: [
+                    1.0 / f32::from(width),
+                    1.0 / f32::from(height),
+                    f32::from(width),
+                    f32::from(height),
+                ],
+                params,
+            };
+
+            self.quad.data.source.0 = source;
+            self.quad.data.out = target;
+            renderer
+                .encoder
+                .update_buffer(&self.quad.data.locals, &[locals], 0)
+                .unwrap();
+            renderer.draw(&mut self.quad, pass);
+
+            source = self.buffers[index % 2].0.clone();
+        }
+    }
+}
+
+#[test]
+fn test_pass_chain() {
+    assert_eq!(
+        pass_chain(&[
+            PostEffect::Vignette,
+            PostEffect::Tonemapping,
+            PostEffect::Fxaa
+        ]),
+        vec![
+            Some(PostEffect::Tonemapping),
+            Some(PostEffect::Fxaa),
+            Some(PostEffect::Vignette),
+        ]
+    );
+    // Without effects the scene is copied to the window as is.
+    assert_eq!(pass_chain(&[]), vec![None]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_render_post_processing() {
+    use super::RendererConfig;
+
+    let mut renderer = Renderer::new_headless(
+        &RendererConfig::new()
+            .with_size(8, 8)
+            .with_post_processing(true),
+    );
+    let mut post_processing = PostProcessing::new(&mut renderer);
+
+    // Tonemapping is only enabled by default for HDR scenes.
+    assert!(!post_processing.is_enabled(PostEffect::Tonemapping));
+    assert!(post_processing.is_enabled(PostEffect::Fxaa));
+    post_processing.toggle(PostEffect::Vignette);
+    assert!(post_processing.is_enabled(PostEffect::Vignette));
+    post_processing.toggle(PostEffect::Vignette);
+    assert!(!post_processing.is_enabled(PostEffect::Vignette));
+
+    for &fxaa in &[true, false] {
+        post_processing.set_enabled(PostEffect::Fxaa, fxaa);
+        renderer.set_clear_color([1.0, 0.0, 1.0, 1.0]);
+        renderer.clear();
+        // The scene keeps the clear color, the last pass must overwrite the window.
+        renderer
+            .encoder
+            .clear(&renderer.render_target, [0.0, 0.0, 0.0, 1.0]);
+        post_processing.render(&mut renderer);
+        renderer.flush();
+
+        let pixels = renderer.read_pixels();
+        assert!(
+            pixels.chunks(4).all(|pixel| pixel == [255, 0, 255, 255]),
+            "fxaa: {}",
+            fxaa
+        );
+    }
+}
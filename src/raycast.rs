@@ -0,0 +1,101 @@
+use cgmath::prelude::*;
+use cgmath::{Point3, Vector3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub block: Point3<i32>,
+    // Normal of the face the ray entered through, zero when starting inside a block.
+    pub normal: Vector3<i32>,
+    pub distance: f32,
+}
+
+/// Walks every grid cell crossed by the ray, in order, until `is_solid`
+/// returns true or `max_distance` is reached.
+pub fn raycast_blocks<F: Fn(Point3<i32>) -> bool>(
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+    is_solid: F,
+) -> Option<RaycastHit> {
+    let direction = direction.normalize();
+    let mut block = Point3::new(
+        origin.x.floor() as i32,
+        origin.y.floor() as i32,
+        origin.z.floor() as i32,
+    );
+    let mut normal = Vector3::zero();
+    let mut step = [0; 3];
+    // Distance along the ray to the next cell boundary, and between two boundaries, on each axis.
+    let mut next_boundary = [std::f32::INFINITY; 3];
+    let mut boundary_delta = [std::f32::INFINITY; 3];
+
+    for axis in 0..3 {
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            next_boundary[axis] = (block[axis] as f32 + 1.0 - origin[axis]) / direction[axis];
+            boundary_delta[axis] = 1.0 / direction[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            next_boundary[axis] = (block[axis] as f32 - origin[axis]) / direction[axis];
+            boundary_delta[axis] = -1.0 / direction[axis];
+        }
+    }
+
+    let mut distance = 0.0;
+
+    while distance <= max_distance {
+        if is_solid(block) {
+            return Some(RaycastHit {
+                block,
+                normal,
+                distance,
+            });
+        }
+
+        let axis = if next_boundary[0] < next_boundary[1] {
+            if next_boundary[0] < next_boundary[2] {
+                0
+            } else {
+                2
+            }
+        } else if next_boundary[1] < next_boundary[2] {
+            1
+        } else {
+            2
+        };
+
+        distance = next_boundary[axis];
+        next_boundary[axis] += boundary_delta[axis];
+        block[axis] += step[axis];
+        normal = Vector3::zero();
+        normal[axis] = -step[axis];
+    }
+
+    None
+}
+
+#[test]
+fn test_raycast_blocks() {
+    let is_solid = |block: Point3<i32>| block.y < 0;
+
+    let hit = raycast_blocks(
+        Point3::new(0.5, 3.5, 0.5),
+        Vector3::new(0.0, -1.0, 0.0),
+        10.0,
+        is_solid,
+    )
+    .unwrap();
+
+    assert_eq!(hit.block, Point3::new(0, -1, 0));
+    assert_eq!(hit.normal, Vector3::new(0, 1, 0));
+    assert_eq!(hit.distance, 3.5);
+
+    let miss = raycast_blocks(
+        Point3::new(0.5, 3.5, 0.5),
+        Vector3::new(1.0, 0.0, 0.0),
+        10.0,
+        is_solid,
+    );
+
+    assert_eq!(miss, None);
+}
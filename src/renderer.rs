@@ -1,13 +1,155 @@
-use super::{ColorFormat, DepthFormat, Events, Mesh, Pipeline, Resources};
-use gfx::Device;
+#[cfg(target_os = "linux")]
+use super::HeadlessContext;
+use super::{
+    ColorFormat, DepthFormat, Events, FrameLocals, Mesh, Pipeline, RendererConfig, Resources,
+    ShadowMaps, WindowMode,
+};
+use gfx::memory::{Bind, Typed, Usage};
+use gfx::traits::FactoryExt;
+use gfx::{Device, Factory as FactoryTrait};
 use gfx_device_gl::Factory;
 use glutin::dpi::LogicalSize;
 use glutin::{GlContext, GlWindow};
+use png::HasParameters;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
 
-const CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+const DEFAULT_CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+
+pub type ColorTexture = gfx::handle::Texture<Resources, gfx::format::R8_G8_B8_A8>;
+pub type HdrColorFormat = gfx::format::Rgba16F;
+
+/// Where the 3D scene is drawn. With post-processing enabled this is an
+/// offscreen texture that the post passes read from, otherwise it aliases the
+/// window targets.
+///
+/// The targets are created again when the window is resized, so meshes drawing
+/// into them take the current ones from the renderer every frame.
+pub struct SceneTargets {
+    pub format: gfx::format::Format,
+    pub color: gfx::handle::RawRenderTargetView<Resources>,
+    pub depth_stencil:
+        gfx::handle::DepthStencilView<Resources, (gfx::format::D24_S8, gfx::format::Unorm)>,
+    // NOTE: Only set when rendering offscreen.
+    pub resource: Option<gfx::handle::ShaderResourceView<Resources, [f32; 4]>>,
+}
+
+impl SceneTargets {
+    fn new(
+        factory: &mut Factory,
+        config: &RendererConfig,
+        render_target: &gfx::handle::RenderTargetView<Resources, ColorFormat>,
+        depth_stencil: &gfx::handle::DepthStencilView<Resources, DepthFormat>,
+    ) -> Self {
+        let offscreen_format = if !config.post_processing {
+            None
+        } else if config.hdr {
+            Some(<HdrColorFormat as gfx::format::Formatted>::get_format())
+        } else {
+            Some(<ColorFormat as gfx::format::Formatted>::get_format())
+        };
+
+        Self::create(factory, offscreen_format, render_target, depth_stencil)
+    }
+
+    /// Creates the targets again for new window targets, offscreen ones at their new size.
+    fn resize(
+        &mut self,
+        factory: &mut Factory,
+        render_target: &gfx::handle::RenderTargetView<Resources, ColorFormat>,
+        depth_stencil: &gfx::handle::DepthStencilView<Resources, DepthFormat>,
+    ) {
+        let offscreen_format = if self.is_offscreen() {
+            Some(self.format)
+        } else {
+            None
+        };
+
+        *self = Self::create(factory, offscreen_format, render_target, depth_stencil);
+    }
+
+    fn create(
+        factory: &mut Factory,
+        offscreen_format: Option<gfx::format::Format>,
+        render_target: &gfx::handle::RenderTargetView<Resources, ColorFormat>,
+        depth_stencil: &gfx::handle::DepthStencilView<Resources, DepthFormat>,
+    ) -> Self {
+        let format = match offscreen_format {
+            Some(format) => format,
+            None => {
+                return Self {
+                    format: <ColorFormat as gfx::format::Formatted>::get_format(),
+                    color: render_target.raw().clone(),
+                    depth_stencil: depth_stencil.clone(),
+                    resource: None,
+                }
+            }
+        };
+        let (width, height, _, _) = render_target.get_dimensions();
+        let texture_info = gfx::texture::Info {
+            kind: gfx::texture::Kind::D2(width, height, gfx::texture::AaMode::Single),
+            levels: 1,
+            format: format.0,
+            bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET,
+            usage: Usage::Data,
+        };
+        let texture = factory
+            .create_texture_raw(texture_info, Some(format.1), None)
+            .unwrap();
+        let resource = factory
+            .view_texture_as_shader_resource_raw(
+                &texture,
+                gfx::texture::ResourceDesc {
+                    channel: format.1,
+                    layer: None,
+                    min: 0,
+                    max: 0,
+                    swizzle: gfx::format::Swizzle::new(),
+                },
+            )
+            .unwrap();
+        let color = factory
+            .view_texture_as_render_target_raw(
+                &texture,
+                gfx::texture::RenderDesc {
+                    channel: format.1,
+                    level: 0,
+                    layer: None,
+                },
+            )
+            .unwrap();
+        let depth_stencil = factory
+            .create_depth_stencil_view_only::<DepthFormat>(width, height)
+            .unwrap();
+
+        Self {
+            format,
+            color,
+            depth_stencil,
+            resource: Some(Typed::new(resource)),
+        }
+    }
+
+    pub fn is_offscreen(&self) -> bool {
+        self.resource.is_some()
+    }
+}
+
+/// What the renderer presents to: an on-screen window, or a headless OSMesa
+/// context whose output only lives in an offscreen color texture.
+pub enum Surface {
+    Window(GlWindow),
+    #[cfg(target_os = "linux")]
+    Headless(HeadlessContext, LogicalSize),
+}
 
 pub struct Renderer {
-    pub window: GlWindow,
+    pub surface: Surface,
+    window_mode: WindowMode,
+    // Mode restored when toggling fullscreen off.
+    windowed_mode: WindowMode,
+    clear_color: [f32; 4],
     pub factory: Factory,
     pub device: gfx_device_gl::Device,
     pub encoder: gfx::Encoder<Resources, gfx_device_gl::CommandBuffer>,
@@ -15,25 +157,50 @@ pub struct Renderer {
         gfx::handle::RenderTargetView<Resources, (gfx::format::R8_G8_B8_A8, gfx::format::Unorm)>,
     pub depth_stencil:
         gfx::handle::DepthStencilView<Resources, (gfx::format::D24_S8, gfx::format::Unorm)>,
+    pub frame_locals: gfx::handle::Buffer<Resources, FrameLocals>,
+    pub shadow_maps: ShadowMaps,
+    pub scene: SceneTargets,
+    // NOTE: Only set for headless renderers, the window framebuffer has no texture.
+    color_texture: Option<ColorTexture>,
+    screenshot_path: Option<PathBuf>,
+    // Outcome of the last capture, until taken by `take_screenshot_result`.
+    screenshot_result: Option<(PathBuf, io::Result<()>)>,
+    draw_calls: usize,
 }
 
 impl Renderer {
     pub fn new(events: &mut Events) -> Self {
-        let window_config = glutin::WindowBuilder::new()
-            .with_title("Triangle example".to_string())
-            .with_dimensions((1024, 768).into());
+        Self::with_config(events, &RendererConfig::default())
+    }
+
+    pub fn with_config(events: &mut Events, config: &RendererConfig) -> Self {
+        let events_loop = events.get_events_loop();
+        let mut window_config = glutin::WindowBuilder::new()
+            .with_title(config.title.clone())
+            .with_dimensions(config.size.into())
+            .with_resizable(config.resizable);
+
+        window_config = match config.window_mode {
+            WindowMode::Windowed => window_config,
+            WindowMode::Fullscreen => {
+                window_config.with_fullscreen(Some(events_loop.get_primary_monitor()))
+            }
+            WindowMode::Borderless => window_config
+                .with_decorations(false)
+                .with_maximized(true),
+        };
 
         let (api, version) = if cfg!(target_os = "emscripten") {
             (glutin::Api::WebGl, (2, 0))
         } else {
-            (glutin::Api::OpenGl, (3, 2))
+            (glutin::Api::OpenGl, config.gl_version)
         };
 
         let context = glutin::ContextBuilder::new()
             .with_gl(glutin::GlRequest::Specific(api, version))
-            .with_vsync(true);
+            .with_vsync(config.vsync)
+            .with_multisampling(config.msaa_samples);
 
-        let events_loop = events.get_events_loop();
         let (window, device, mut factory, mut render_target, mut depth_stencil) =
             gfx_window_glutin::init::<ColorFormat, DepthFormat>(
                 window_config,
@@ -41,6 +208,8 @@ impl Renderer {
                 events_loop,
             );
         let encoder = gfx::Encoder::from(factory.create_command_buffer());
+        let frame_locals = factory.create_constant_buffer(1);
+        let shadow_maps = ShadowMaps::new(&mut factory, config.shadow_map_size);
 
         // FIXME: On Mac 10.14 (Mojave) we need to resize the window after creation.
         // This is related to this issue https://github.com/tomaka/glutin/issues/1069
@@ -49,21 +218,139 @@ impl Renderer {
         let physical_size = logical_size.to_physical(window.get_hidpi_factor());
         window.resize(physical_size);
         gfx_window_glutin::update_views(&window, &mut render_target, &mut depth_stencil);
+        let scene = SceneTargets::new(&mut factory, config, &render_target, &depth_stencil);
+
+        Self {
+            surface: Surface::Window(window),
+            window_mode: config.window_mode,
+            windowed_mode: match config.window_mode {
+                WindowMode::Fullscreen => WindowMode::Windowed,
+                window_mode => window_mode,
+            },
+            clear_color: DEFAULT_CLEAR_COLOR,
+            factory,
+            device,
+            encoder,
+            render_target,
+            depth_stencil,
+            frame_locals,
+            shadow_maps,
+            scene,
+            color_texture: None,
+            screenshot_path: None,
+            screenshot_result: None,
+            draw_calls: 0,
+        }
+    }
+
+    /// Creates a renderer without a window, drawing into an offscreen
+    /// framebuffer of `config.size` that can be read back with `read_pixels`.
+    /// Window settings of `config` are ignored.
+    ///
+    /// The context is created through OSMesa, so this works with Mesa's
+    /// software rasterizer on machines without a GPU or a display server.
+    #[cfg(target_os = "linux")]
+    pub fn new_headless(config: &RendererConfig) -> Self {
+        let (width, height) = config.size;
+        let mut context = HeadlessContext::new(width, height, config.gl_version)
+            .expect("Failed to create headless context");
+
+        unsafe {
+            context.make_current().unwrap();
+        }
+
+        let (device, mut factory) =
+            gfx_device_gl::create(|symbol| context.get_proc_address(symbol) as *const _);
+        let encoder = gfx::Encoder::from(factory.create_command_buffer());
+        let frame_locals = factory.create_constant_buffer(1);
+        let shadow_maps = ShadowMaps::new(&mut factory, config.shadow_map_size);
+
+        let kind = gfx::texture::Kind::D2(
+            width as gfx::texture::Size,
+            height as gfx::texture::Size,
+            gfx::texture::AaMode::Single,
+        );
+        let color_texture = factory
+            .create_texture::<gfx::format::R8_G8_B8_A8>(
+                kind,
+                1,
+                Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+                Usage::Data,
+                Some(gfx::format::ChannelType::Unorm),
+            )
+            .unwrap();
+        let render_target = factory
+            .view_texture_as_render_target::<ColorFormat>(&color_texture, 0, None)
+            .unwrap();
+        let depth_stencil = factory
+            .create_depth_stencil_view_only::<DepthFormat>(
+                width as gfx::texture::Size,
+                height as gfx::texture::Size,
+            )
+            .unwrap();
+        let scene = SceneTargets::new(&mut factory, config, &render_target, &depth_stencil);
 
         Self {
-            window,
+            surface: Surface::Headless(
+                context,
+                LogicalSize::new(f64::from(width), f64::from(height)),
+            ),
+            window_mode: WindowMode::Windowed,
+            windowed_mode: WindowMode::Windowed,
+            clear_color: DEFAULT_CLEAR_COLOR,
             factory,
             device,
             encoder,
             render_target,
             depth_stencil,
+            frame_locals,
+            shadow_maps,
+            scene,
+            color_texture: Some(color_texture),
+            screenshot_path: None,
+            screenshot_result: None,
+            draw_calls: 0,
+        }
+    }
+
+    /// Size of the drawable area, excluding window decorations.
+    pub fn get_size(&self) -> LogicalSize {
+        match self.surface {
+            Surface::Window(ref window) => window.get_inner_size().expect("Window no longer exists"),
+            #[cfg(target_os = "linux")]
+            Surface::Headless(_, size) => size,
         }
     }
 
+    pub fn get_clear_color(&self) -> [f32; 4] {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: [f32; 4]) {
+        self.clear_color = clear_color;
+    }
+
+    pub fn update_frame_locals(&mut self, frame_locals: &FrameLocals) {
+        self.encoder
+            .update_buffer(&self.frame_locals, &[*frame_locals], 0)
+            .unwrap();
+    }
+
     pub fn clear(&mut self) {
-        self.encoder.clear(&self.render_target, CLEAR_COLOR);
+        self.draw_calls = 0;
+        self.encoder.clear(&self.render_target, self.clear_color);
         self.encoder.clear_depth(&self.depth_stencil, 1.0);
         self.encoder.clear_stencil(&self.depth_stencil, 0);
+
+        if self.scene.is_offscreen() {
+            // NOTE: The encoder only clears typed views, the channel type is irrelevant for float clear values.
+            let scene_color: gfx::handle::RenderTargetView<Resources, ColorFormat> =
+                Typed::new(self.scene.color.clone());
+
+            self.encoder.clear(&scene_color, self.clear_color);
+            self.encoder.clear_depth(&self.scene.depth_stencil, 1.0);
+            self.encoder.clear_stencil(&self.scene.depth_stencil, 0);
+        }
     }
 
     pub fn draw<PD: gfx::pso::PipelineData<Resources>, P: Pipeline<PD>>(
@@ -72,21 +359,201 @@ impl Renderer {
         pipe: &P,
     ) {
         self.encoder.draw::<PD>(&mesh.get_slice(), &pipe.get_pso(), &mesh.get_data());
+        self.draw_calls += 1;
+    }
+
+    /// Number of `draw` calls since the last `clear`.
+    pub fn get_draw_calls(&self) -> usize {
+        self.draw_calls
     }
 
     pub fn flush(&mut self) {
         self.encoder.flush(&mut self.device);
-        self.window.swap_buffers().unwrap();
+
+        if let Some(path) = self.screenshot_path.take() {
+            let result = self.save_screenshot(&path);
+            self.screenshot_result = Some((path, result));
+        }
+
+        if let Surface::Window(ref window) = self.surface {
+            window.swap_buffers().unwrap();
+        }
         self.device.cleanup();
     }
 
+    /// Physical pixels per logical pixel.
+    pub fn get_hidpi_factor(&self) -> f64 {
+        match self.surface {
+            Surface::Window(ref window) => window.get_hidpi_factor(),
+            #[cfg(target_os = "linux")]
+            Surface::Headless(..) => 1.0,
+        }
+    }
+
+    /// Size of the color target in pixels.
+    pub fn get_framebuffer_size(&self) -> (u32, u32) {
+        match self.surface {
+            Surface::Window(ref window) => {
+                let physical_size = self.get_size().to_physical(window.get_hidpi_factor());
+                (physical_size.width as u32, physical_size.height as u32)
+            }
+            #[cfg(target_os = "linux")]
+            Surface::Headless(_, size) => (size.width as u32, size.height as u32),
+        }
+    }
+
+    /// Reads back the color target as RGBA bytes, row by row starting from the
+    /// top of the image.
+    ///
+    /// For a window this reads the back buffer, so it must be called after the
+    /// frame has been drawn and before it is presented.
+    pub fn read_pixels(&mut self) -> Vec<u8> {
+        let (width, height) = self.get_framebuffer_size();
+        let (width, height) = (width as usize, height as usize);
+        let mut pixels = vec![0; width * height * 4];
+
+        match self.color_texture {
+            Some(ref texture) => {
+                let image_info = texture
+                    .get_info()
+                    .to_raw_image_info(gfx::format::ChannelType::Unorm, 0);
+                let download_buffer = self
+                    .factory
+                    .create_download_buffer::<[u8; 4]>(width * height)
+                    .unwrap();
+                self.encoder
+                    .copy_texture_to_buffer_raw(
+                        texture.raw(),
+                        None,
+                        image_info,
+                        download_buffer.raw(),
+                        0,
+                    )
+                    .unwrap();
+                self.encoder.flush(&mut self.device);
+
+                let reader = self.factory.read_mapping(&download_buffer).unwrap();
+                for (pixel, bytes) in reader.iter().zip(pixels.chunks_mut(4)) {
+                    bytes.copy_from_slice(pixel);
+                }
+            }
+            None => unsafe {
+                self.device.with_gl(|gl| {
+                    gl.BindFramebuffer(gfx_gl::READ_FRAMEBUFFER, 0);
+                    gl.ReadBuffer(gfx_gl::BACK);
+                    gl.PixelStorei(gfx_gl::PACK_ALIGNMENT, 1);
+                    gl.ReadPixels(
+                        0,
+                        0,
+                        width as i32,
+                        height as i32,
+                        gfx_gl::RGBA,
+                        gfx_gl::UNSIGNED_BYTE,
+                        pixels.as_mut_ptr() as *mut _,
+                    );
+                });
+            },
+        }
+
+        // NOTE: OpenGL stores rows bottom to top.
+        let row_length = width * 4;
+        for y in 0..height / 2 {
+            let (top, bottom) = pixels.split_at_mut((height - y - 1) * row_length);
+            top[y * row_length..(y + 1) * row_length].swap_with_slice(&mut bottom[..row_length]);
+        }
+
+        pixels
+    }
+
+    /// Saves the next presented frame to `path` as a PNG.
+    ///
+    /// The capture is taken during the next `flush`, once the frame is complete,
+    /// its outcome is then returned by `take_screenshot_result`.
+    pub fn capture_screenshot<P: AsRef<Path>>(&mut self, path: P) {
+        self.screenshot_path = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Path and outcome of the last screenshot saved by `flush`, if not taken yet.
+    pub fn take_screenshot_result(&mut self) -> Option<(PathBuf, io::Result<()>)> {
+        self.screenshot_result.take()
+    }
+
+    fn save_screenshot(&mut self, path: &Path) -> io::Result<()> {
+        let (width, height) = self.get_framebuffer_size();
+        let pixels = self.read_pixels();
+
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+
+        Ok(())
+    }
+
+    pub fn get_window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+
+    pub fn set_window_mode(&mut self, window_mode: WindowMode) {
+        if let Surface::Window(ref window) = self.surface {
+            match window_mode {
+                WindowMode::Windowed => {
+                    window.set_fullscreen(None);
+                    window.set_maximized(false);
+                    window.set_decorations(true);
+                }
+                WindowMode::Fullscreen => {
+                    window.set_fullscreen(Some(window.get_current_monitor()));
+                }
+                WindowMode::Borderless => {
+                    window.set_fullscreen(None);
+                    window.set_decorations(false);
+                    window.set_maximized(true);
+                }
+            }
+        }
+
+        if window_mode != WindowMode::Fullscreen {
+            self.windowed_mode = window_mode;
+        }
+        self.window_mode = window_mode;
+    }
+
+    /// Switches to fullscreen, or back to the windowed or borderless mode used before.
+    pub fn toggle_fullscreen(&mut self) {
+        let window_mode = match self.window_mode {
+            WindowMode::Fullscreen => self.windowed_mode,
+            _ => WindowMode::Fullscreen,
+        };
+
+        self.set_window_mode(window_mode);
+    }
+
     pub fn resize(&mut self, size: LogicalSize) {
-        self.window
-            .resize(size.to_physical(self.window.get_hidpi_factor()));
-        gfx_window_glutin::update_views(
-            &self.window,
-            &mut self.render_target,
-            &mut self.depth_stencil,
-        );
+        if let Surface::Window(ref window) = self.surface {
+            window.resize(size.to_physical(window.get_hidpi_factor()));
+            gfx_window_glutin::update_views(
+                window,
+                &mut self.render_target,
+                &mut self.depth_stencil,
+            );
+            self.scene
+                .resize(&mut self.factory, &self.render_target, &self.depth_stencil);
+        }
     }
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_headless_read_pixels() {
+    let mut renderer = Renderer::new_headless(&RendererConfig::new().with_size(4, 2));
+
+    renderer.set_clear_color([1.0, 0.0, 1.0, 1.0]);
+    renderer.clear();
+    renderer.flush();
+
+    let pixels = renderer.read_pixels();
+    assert_eq!(pixels.len(), 4 * 2 * 4);
+    assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 255, 255]));
+}
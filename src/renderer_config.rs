@@ -0,0 +1,236 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowMode {
+    Windowed,
+    Fullscreen,
+    // Undecorated window covering the whole monitor.
+    Borderless,
+}
+
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    pub title: String,
+    pub size: (u32, u32),
+    pub window_mode: WindowMode,
+    pub vsync: bool,
+    // NOTE: 0 disables multisampling. Only the window is multisampled, so this
+    // has no effect with `post_processing`, whose offscreen scene is single sampled.
+    pub msaa_samples: u16,
+    pub gl_version: (u8, u8),
+    pub resizable: bool,
+    // Size in pixels of each shadow cascade.
+    pub shadow_map_size: u16,
+    // Draws the scene offscreen so `PostProcessing` passes can run before presenting.
+    pub post_processing: bool,
+    // Uses a floating point offscreen target, only meaningful with post-processing.
+    pub hdr: bool,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            title: "Terrain generation".to_string(),
+            size: (1024, 768),
+            window_mode: WindowMode::Windowed,
+            vsync: true,
+            msaa_samples: 0,
+            gl_version: (3, 2),
+            resizable: true,
+            shadow_map_size: 2048,
+            post_processing: false,
+            hdr: false,
+        }
+    }
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid renderer config line: {}", line),
+    )
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Option<(T, T)> {
+    let mut parts = value.split(separator).map(|part| part.trim().parse::<T>());
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(first)), Some(Ok(second)), None) => Some((first, second)),
+        _ => None,
+    }
+}
+
+impl RendererConfig {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Loads a config from a file of `key = value` lines, `#` starts a comment.
+    /// Missing keys keep their default value.
+    ///
+    /// ```text
+    /// title = My game
+    /// size = 1280x720
+    /// window_mode = borderless
+    /// vsync = false
+    /// msaa_samples = 4
+    /// gl_version = 3.3
+    /// resizable = true
+    /// shadow_map_size = 4096
+    /// post_processing = true
+    /// hdr = false
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses the content of a config file, see `from_file`.
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut config = Self::default();
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut key_value = line.splitn(2, '=');
+            let key = key_value.next().unwrap().trim();
+            let value = key_value.next().ok_or_else(|| invalid_data(line))?.trim();
+
+            match key {
+                "title" => config.title = value.to_string(),
+                "size" => config.size = parse_pair(value, 'x').ok_or_else(|| invalid_data(line))?,
+                "window_mode" => {
+                    config.window_mode = match value {
+                        "windowed" => WindowMode::Windowed,
+                        "fullscreen" => WindowMode::Fullscreen,
+                        "borderless" => WindowMode::Borderless,
+                        _ => return Err(invalid_data(line)),
+                    }
+                }
+                "vsync" => config.vsync = value.parse().map_err(|_| invalid_data(line))?,
+                "msaa_samples" => {
+                    config.msaa_samples = value.parse().map_err(|_| invalid_data(line))?
+                }
+                "gl_version" => {
+                    config.gl_version = parse_pair(value, '.').ok_or_else(|| invalid_data(line))?
+                }
+                "resizable" => config.resizable = value.parse().map_err(|_| invalid_data(line))?,
+                "shadow_map_size" => {
+                    config.shadow_map_size = value.parse().map_err(|_| invalid_data(line))?
+                }
+                "post_processing" => {
+                    config.post_processing = value.parse().map_err(|_| invalid_data(line))?
+                }
+                "hdr" => config.hdr = value.parse().map_err(|_| invalid_data(line))?,
+                _ => return Err(invalid_data(line)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    pub fn with_window_mode(mut self, window_mode: WindowMode) -> Self {
+        self.window_mode = window_mode;
+        self
+    }
+
+    pub fn with_vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn with_msaa_samples(mut self, msaa_samples: u16) -> Self {
+        self.msaa_samples = msaa_samples;
+        self
+    }
+
+    pub fn with_gl_version(mut self, major: u8, minor: u8) -> Self {
+        self.gl_version = (major, minor);
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn with_shadow_map_size(mut self, shadow_map_size: u16) -> Self {
+        self.shadow_map_size = shadow_map_size;
+        self
+    }
+
+    pub fn with_post_processing(mut self, post_processing: bool) -> Self {
+        self.post_processing = post_processing;
+        self
+    }
+
+    pub fn with_hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+}
+
+#[test]
+fn test_parse_valid_keys() {
+    let config = RendererConfig::parse(
+        "# Comment\n\
+         title = My game # trailing comment\n\
+         size = 1280x720\n\
+         \n\
+         window_mode = borderless\n\
+         vsync = false\n\
+         msaa_samples = 4\n\
+         gl_version = 3.3\n\
+         shadow_map_size = 4096\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.title, "My game");
+    assert_eq!(config.size, (1280, 720));
+    assert_eq!(config.window_mode, WindowMode::Borderless);
+    assert!(!config.vsync);
+    assert_eq!(config.msaa_samples, 4);
+    assert_eq!(config.gl_version, (3, 3));
+    assert_eq!(config.shadow_map_size, 4096);
+    // Missing keys keep their default value.
+    assert_eq!(config.resizable, RendererConfig::default().resizable);
+}
+
+#[test]
+fn test_parse_unknown_key() {
+    let error = RendererConfig::parse("colour = red").unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_parse_bad_values() {
+    for line in &[
+        "size = 1280",
+        "size = 1280x720x2",
+        "window_mode = maximized",
+        "vsync = yes",
+        "msaa_samples = -1",
+        "gl_version = 3",
+        "hdr",
+    ] {
+        let error = RendererConfig::parse(line).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", line);
+    }
+}
@@ -0,0 +1,55 @@
+use super::{box_wireframe_vertices, LineMesh, LineMeshPipe, Renderer};
+use cgmath::{Matrix4, Point3, Vector3};
+
+// NOTE: Slightly larger than a block so the outline stays visible on every side of it.
+const OUTLINE_MARGIN: f32 = 0.005;
+const OUTLINE_COLOR: [f32; 3] = [0.05, 0.05, 0.05];
+
+/// Wireframe outline around the block targeted by the player.
+pub struct SelectionHighlight {
+    mesh: LineMesh,
+    target: Option<Point3<i32>>,
+}
+
+impl SelectionHighlight {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let vertices = box_wireframe_vertices(
+            [-OUTLINE_MARGIN; 3],
+            [1.0 + OUTLINE_MARGIN; 3],
+            OUTLINE_COLOR,
+        );
+
+        Self {
+            mesh: LineMesh::new(renderer, &vertices, Matrix4::from_scale(1.0)),
+            target: None,
+        }
+    }
+
+    pub fn get_target(&self) -> Option<Point3<i32>> {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: Option<Point3<i32>>) {
+        self.target = target;
+    }
+
+    /// Draws the outline if a block is targeted, to be called after terrain.
+    pub fn draw(
+        &mut self,
+        renderer: &mut Renderer,
+        pipe: &LineMeshPipe,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+    ) {
+        let target = match self.target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let position = Vector3::new(target.x as f32, target.y as f32, target.z as f32);
+
+        self.mesh.set_transform(Matrix4::from_translation(position));
+        self.mesh.update_locals(renderer, view, proj);
+        renderer.draw(&mut self.mesh, pipe);
+    }
+}
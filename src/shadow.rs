@@ -0,0 +1,357 @@
+use super::gfx;
+use super::{Camera, FrameLocals, Mesh, Pipeline, Renderer, Resources, Vertex, VoxelMesh};
+use cgmath::prelude::*;
+use cgmath::{Deg, Matrix4, Point3, Vector3};
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+
+pub const SHADOW_CASCADE_COUNT: usize = 3;
+// Far end of each cascade, as a fraction of the shadow distance.
+const CASCADE_SPLITS: [f32; SHADOW_CASCADE_COUNT] = [0.08, 0.3, 1.0];
+// How far behind a cascade occluders are still rendered into it.
+const CASTER_DISTANCE: f32 = 100.0;
+
+pub type ShadowDepthFormat = gfx::format::Depth;
+
+gfx_defines! {
+    constant Locals {
+        model: [[f32; 4]; 4] = "u_Model",
+        light_view_proj: [[f32; 4]; 4] = "u_LightViewProj",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = "Locals",
+        out_depth: gfx::DepthTarget<ShadowDepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
+}
+
+/// Depth texture array holding one shadow map per cascade.
+pub struct ShadowMaps {
+    pub resource: gfx::handle::ShaderResourceView<Resources, f32>,
+    pub sampler: gfx::handle::Sampler<Resources>,
+    pub targets: Vec<gfx::handle::DepthStencilView<Resources, ShadowDepthFormat>>,
+}
+
+impl ShadowMaps {
+    pub fn new(factory: &mut gfx_device_gl::Factory, size: u16) -> Self {
+        let kind = gfx::texture::Kind::D2Array(
+            size,
+            size,
+            SHADOW_CASCADE_COUNT as gfx::texture::Layer,
+            gfx::texture::AaMode::Single,
+        );
+        let texture = factory
+            .create_texture(
+                kind,
+                1,
+                gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::DEPTH_STENCIL,
+                gfx::memory::Usage::Data,
+                Some(gfx::format::ChannelType::Unorm),
+            )
+            .unwrap();
+        let resource = factory
+            .view_texture_as_shader_resource::<ShadowDepthFormat>(
+                &texture,
+                (0, 0),
+                gfx::format::Swizzle::new(),
+            )
+            .unwrap();
+        let sampler = factory.create_sampler(gfx::texture::SamplerInfo {
+            comparison: Some(gfx::state::Comparison::LessEqual),
+            ..gfx::texture::SamplerInfo::new(
+                gfx::texture::FilterMethod::Bilinear,
+                gfx::texture::WrapMode::Clamp,
+            )
+        });
+        let targets = (0..SHADOW_CASCADE_COUNT)
+            .map(|layer| {
+                factory
+                    .view_texture_as_depth_stencil(
+                        &texture,
+                        0,
+                        Some(layer as gfx::texture::Layer),
+                        gfx::texture::DepthStencilFlags::empty(),
+                    )
+                    .unwrap()
+            })
+            .collect();
+
+        Self {
+            resource,
+            sampler,
+            targets,
+        }
+    }
+}
+
+pub struct ShadowPipe {
+    pub pso: gfx::PipelineState<Resources, pipe::Meta>,
+}
+
+impl ShadowPipe {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let vs_code = include_bytes!("shader/shadow_150_core.glslv").to_vec();
+        let fs_code = include_bytes!("shader/shadow_150_core.glslf").to_vec();
+
+        let pso = renderer
+            .factory
+            .create_pipeline_simple(&vs_code, &fs_code, pipe::new())
+            .unwrap();
+
+        Self { pso }
+    }
+}
+
+impl Pipeline<pipe::Data<Resources>> for ShadowPipe {
+    fn get_pso(&self) -> &gfx::PipelineState<Resources, pipe::Meta> {
+        &self.pso
+    }
+}
+
+/// Depth-only copy of a voxel mesh, drawn into every cascade by `ShadowCascades::draw`.
+#[derive(Clone, Debug)]
+pub struct ShadowCaster {
+    pub slice: gfx::Slice<Resources>,
+    pub data: pipe::Data<Resources>,
+    transform: Matrix4<f32>,
+}
+
+impl ShadowCaster {
+    pub fn new(renderer: &mut Renderer, mesh: &VoxelMesh) -> Self {
+        let data = pipe::Data {
+            vbuf: mesh.data.vbuf.clone(),
+            locals: renderer.factory.create_constant_buffer(1),
+            out_depth: renderer.shadow_maps.targets[0].clone(),
+        };
+
+        Self {
+            slice: mesh.slice.clone(),
+            data,
+            transform: mesh.get_transform(),
+        }
+    }
+}
+
+impl Mesh<pipe::Data<Resources>> for ShadowCaster {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+/// World space corners of the part of a perspective view frustum between
+/// `near` and `far`, near corners first.
+pub fn frustum_corners(
+    view: &Matrix4<f32>,
+    field_of_view: Deg<f32>,
+    aspect_ratio: f32,
+    near: f32,
+    far: f32,
+) -> Vec<Point3<f32>> {
+    let inv_view = view.invert().unwrap_or_else(Matrix4::identity);
+    let tan_half_fov = (field_of_view / 2.0).tan();
+    let mut corners = Vec::with_capacity(8);
+
+    for &distance in &[near, far] {
+        let half_height = distance * tan_half_fov;
+        let half_width = half_height * aspect_ratio;
+
+        for &(x, y) in &[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let view_corner = Point3::new(x * half_width, y * half_height, -distance);
+            corners.push(inv_view.transform_point(view_corner));
+        }
+    }
+
+    corners
+}
+
+/// Fits an orthographic sun projection around the part of the camera
+/// frustum between `near` and `far`, one cascade.
+pub fn cascade_light_view_proj(
+    view: &Matrix4<f32>,
+    field_of_view: Deg<f32>,
+    aspect_ratio: f32,
+    near: f32,
+    far: f32,
+    sun_direction: Vector3<f32>,
+) -> Matrix4<f32> {
+    let corners = frustum_corners(view, field_of_view, aspect_ratio, near, far);
+    let center = Point3::centroid(&corners);
+    // NOTE: Fitting a sphere instead of a box keeps the projection size stable when the camera rotates.
+    let radius = corners
+        .iter()
+        .map(|corner| corner.distance(center))
+        .fold(0.0, f32::max);
+
+    let sun_direction = sun_direction.normalize();
+    let up = if sun_direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+    let eye = center + sun_direction * (radius + CASTER_DISTANCE);
+    let light_view = Matrix4::look_at(eye, center, up);
+    let light_proj = cgmath::ortho(
+        -radius,
+        radius,
+        -radius,
+        radius,
+        0.0,
+        2.0 * radius + CASTER_DISTANCE,
+    );
+
+    light_proj * light_view
+}
+
+pub struct ShadowCascades {
+    shadow_distance: f32,
+    light_view_projs: [Matrix4<f32>; SHADOW_CASCADE_COUNT],
+    enabled: bool,
+}
+
+impl ShadowCascades {
+    pub fn new(shadow_distance: f32) -> Self {
+        Self {
+            shadow_distance,
+            light_view_projs: [Matrix4::identity(); SHADOW_CASCADE_COUNT],
+            enabled: false,
+        }
+    }
+
+    pub fn get_split(&self, cascade: usize) -> f32 {
+        CASCADE_SPLITS[cascade] * self.shadow_distance
+    }
+
+    pub fn get_light_view_proj(&self, cascade: usize) -> Matrix4<f32> {
+        self.light_view_projs[cascade]
+    }
+
+    pub fn update(&mut self, camera: &Camera, sun_direction: Vector3<f32>) {
+        // NOTE: There is no direct light to occlude once the sun is below the horizon.
+        self.enabled = sun_direction.y > 0.0;
+
+        if !self.enabled {
+            return;
+        }
+
+        let view = camera.get_view();
+        let mut near = camera.get_near();
+
+        for cascade in 0..SHADOW_CASCADE_COUNT {
+            let far = self.get_split(cascade);
+
+            self.light_view_projs[cascade] = cascade_light_view_proj(
+                &view,
+                camera.get_field_of_view(),
+                camera.get_aspect_ratio(),
+                near,
+                far,
+                sun_direction,
+            );
+            near = far;
+        }
+    }
+
+    pub fn update_frame_locals(&self, frame_locals: &mut FrameLocals) {
+        frame_locals.shadow_matrix_0 = self.light_view_projs[0].into();
+        frame_locals.shadow_matrix_1 = self.light_view_projs[1].into();
+        frame_locals.shadow_matrix_2 = self.light_view_projs[2].into();
+        frame_locals.shadow_splits = [
+            self.get_split(0),
+            self.get_split(1),
+            self.get_split(2),
+            if self.enabled { 1.0 } else { 0.0 },
+        ];
+    }
+
+    /// Clears every cascade, to be called once per frame before drawing casters.
+    pub fn clear(&self, renderer: &mut Renderer) {
+        for target in &renderer.shadow_maps.targets {
+            renderer.encoder.clear_depth(target, 1.0);
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, caster: &mut ShadowCaster, pipe: &ShadowPipe) {
+        if !self.enabled {
+            return;
+        }
+
+        for cascade in 0..SHADOW_CASCADE_COUNT {
+            let locals = Locals {
+                model: caster.transform.into(),
+                light_view_proj: self.light_view_projs[cascade].into(),
+            };
+
+            caster.data.out_depth = renderer.shadow_maps.targets[cascade].clone();
+            renderer
+                .encoder
+                .update_buffer(&caster.data.locals, &[locals], 0)
+                .unwrap();
+            renderer.draw(caster, pipe);
+        }
+    }
+}
+
+#[test]
+fn test_cascade_splits() {
+    let shadow_cascades = ShadowCascades::new(150.0);
+    let splits: Vec<f32> = (0..SHADOW_CASCADE_COUNT)
+        .map(|cascade| shadow_cascades.get_split(cascade))
+        .collect();
+
+    assert!(splits[0] > 0.0);
+    assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(splits[SHADOW_CASCADE_COUNT - 1], 150.0);
+}
+
+#[test]
+fn test_cascade_light_view_proj_contains_cascade() {
+    let shadow_cascades = ShadowCascades::new(150.0);
+    let view = Matrix4::look_at(
+        Point3::new(10.0, 20.0, 30.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::unit_y(),
+    );
+    let field_of_view = Deg(60.0);
+    let aspect_ratio = 16.0 / 9.0;
+
+    for &sun_direction in &[
+        Vector3::new(0.3, 1.0, 0.5),
+        Vector3::new(-1.0, 0.1, 0.0),
+        Vector3::unit_y(),
+    ] {
+        let mut near = 0.1;
+
+        for cascade in 0..SHADOW_CASCADE_COUNT {
+            let far = shadow_cascades.get_split(cascade);
+            let light_view_proj = cascade_light_view_proj(
+                &view,
+                field_of_view,
+                aspect_ratio,
+                near,
+                far,
+                sun_direction,
+            );
+
+            for corner in frustum_corners(&view, field_of_view, aspect_ratio, near, far) {
+                let clip = light_view_proj.transform_point(corner);
+
+                assert!(
+                    clip.x.abs() <= 1.0 + 1e-4
+                        && clip.y.abs() <= 1.0 + 1e-4
+                        && clip.z.abs() <= 1.0 + 1e-4,
+                    "cascade {} corner {:?} is outside the light volume at {:?}",
+                    cascade,
+                    corner,
+                    clip
+                );
+            }
+
+            near = far;
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use super::gfx;
+use super::{Mesh, Pipeline, Renderer, Resources, TimeOfDay};
+use cgmath::prelude::*;
+use cgmath::Matrix4;
+use gfx::traits::FactoryExt;
+
+gfx_defines! {
+    vertex Vertex {
+        pos: [f32; 2] = "a_Pos",
+    }
+
+    constant Locals {
+        inv_view_proj: [[f32; 4]; 4] = "u_InvViewProj",
+        horizon_color: [f32; 4] = "u_HorizonColor",
+        zenith_color: [f32; 4] = "u_ZenithColor",
+        sun_direction: [f32; 4] = "u_SunDirection",
+        sun_color: [f32; 4] = "u_SunColor",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = "Locals",
+        out: gfx::RawRenderTarget = (
+            "Target0",
+            gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Unorm),
+            gfx::state::ColorMask::all(),
+            None
+        ),
+    }
+}
+
+// Sky colors at midnight, sunrise, noon and sunset.
+const KEYFRAMES: [SkyColors; 4] = [
+    SkyColors {
+        horizon: [0.02, 0.03, 0.08],
+        zenith: [0.0, 0.0, 0.02],
+        sun: [0.0, 0.0, 0.0],
+    },
+    SkyColors {
+        horizon: [0.9, 0.5, 0.3],
+        zenith: [0.2, 0.3, 0.5],
+        sun: [1.0, 0.6, 0.3],
+    },
+    SkyColors {
+        horizon: [0.6, 0.75, 0.9],
+        zenith: [0.15, 0.35, 0.75],
+        sun: [1.0, 1.0, 0.9],
+    },
+    SkyColors {
+        horizon: [0.9, 0.4, 0.25],
+        zenith: [0.15, 0.2, 0.4],
+        sun: [1.0, 0.5, 0.2],
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyColors {
+    pub horizon: [f32; 3],
+    pub zenith: [f32; 3],
+    pub sun: [f32; 3],
+}
+
+#[inline]
+fn lerp_color(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+impl SkyColors {
+    /// `time_of_day` goes from 0 to 1 over a day: 0 is midnight, 0.25 sunrise,
+    /// 0.5 noon and 0.75 sunset.
+    pub fn from_time_of_day(time_of_day: f32) -> Self {
+        let scaled = (time_of_day - time_of_day.floor()) * KEYFRAMES.len() as f32;
+        let index = scaled.floor() as usize % KEYFRAMES.len();
+        let from = &KEYFRAMES[index];
+        let to = &KEYFRAMES[(index + 1) % KEYFRAMES.len()];
+        let t = scaled - scaled.floor();
+
+        Self {
+            horizon: lerp_color(from.horizon, to.horizon, t),
+            zenith: lerp_color(from.zenith, to.zenith, t),
+            sun: lerp_color(from.sun, to.sun, t),
+        }
+    }
+}
+
+pub struct SkyPipe {
+    pub pso: gfx::PipelineState<Resources, pipe::Meta>,
+}
+
+impl SkyPipe {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let vs_code = include_bytes!("shader/sky_150_core.glslv").to_vec();
+        let fs_code = include_bytes!("shader/sky_150_core.glslf").to_vec();
+
+        let init = pipe::Init {
+            out: (
+                "Target0",
+                renderer.scene.format,
+                gfx::state::ColorMask::all(),
+                None,
+            ),
+            ..pipe::new()
+        };
+
+        let pso = renderer
+            .factory
+            .create_pipeline_simple(&vs_code, &fs_code, init)
+            .unwrap();
+
+        Self { pso }
+    }
+}
+
+impl Pipeline<pipe::Data<Resources>> for SkyPipe {
+    fn get_pso(&self) -> &gfx::PipelineState<Resources, pipe::Meta> {
+        &self.pso
+    }
+}
+
+/// Full-screen quad drawn before the terrain, shading each pixel from the
+/// direction it looks at.
+#[derive(Clone, Debug)]
+pub struct Sky {
+    pub slice: gfx::Slice<Resources>,
+    pub data: pipe::Data<Resources>,
+}
+
+impl Sky {
+    pub fn new(renderer: &mut Renderer) -> Self {
+        let vertices: &[Vertex] = &[
+            Vertex { pos: [-1.0, -1.0] },
+            Vertex { pos: [1.0, -1.0] },
+            Vertex { pos: [1.0, 1.0] },
+            Vertex { pos: [-1.0, 1.0] },
+        ];
+
+        const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
+
+        let (vbuf, slice) = renderer
+            .factory
+            .create_vertex_buffer_with_slice(vertices, INDICES);
+
+        let data = pipe::Data {
+            vbuf,
+            locals: renderer.factory.create_constant_buffer(1),
+            out: renderer.scene.color.clone(),
+        };
+
+        Self { data, slice }
+    }
+
+    pub fn update_locals(
+        &mut self,
+        renderer: &mut Renderer,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+        time_of_day: &TimeOfDay,
+    ) {
+        let colors = time_of_day.get_sky_colors();
+        let sun_direction = time_of_day.get_sun_direction();
+        let inv_view_proj = (proj * view).invert().unwrap_or_else(Matrix4::identity);
+
+        let locals = Locals {
+            inv_view_proj: inv_view_proj.into(),
+            horizon_color: [colors.horizon[0], colors.horizon[1], colors.horizon[2], 1.0],
+            zenith_color: [colors.zenith[0], colors.zenith[1], colors.zenith[2], 1.0],
+            sun_direction: [sun_direction.x, sun_direction.y, sun_direction.z, 0.0],
+            sun_color: [colors.sun[0], colors.sun[1], colors.sun[2], 1.0],
+        };
+
+        renderer
+            .encoder
+            .update_buffer(&self.data.locals, &[locals], 0)
+            .unwrap();
+        self.data.out = renderer.scene.color.clone();
+    }
+}
+
+impl Mesh<pipe::Data<Resources>> for Sky {
+    fn get_data(&self) -> &pipe::Data<Resources> {
+        &self.data
+    }
+
+    fn get_slice(&self) -> &gfx::Slice<Resources> {
+        &self.slice
+    }
+}
+
+#[cfg(test)]
+fn assert_colors_eq(colors: SkyColors, expected: SkyColors) {
+    let channels = |colors: SkyColors| {
+        let mut channels = Vec::with_capacity(9);
+        channels.extend_from_slice(&colors.horizon);
+        channels.extend_from_slice(&colors.zenith);
+        channels.extend_from_slice(&colors.sun);
+        channels
+    };
+
+    for (channel, expected_channel) in channels(colors).into_iter().zip(channels(expected)) {
+        assert!(
+            (channel - expected_channel).abs() < 1e-5,
+            "{:?} != {:?}",
+            colors,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_sky_colors_keyframes() {
+    for (i, &keyframe) in KEYFRAMES.iter().enumerate() {
+        let time_of_day = i as f32 / KEYFRAMES.len() as f32;
+
+        assert_eq!(SkyColors::from_time_of_day(time_of_day), keyframe);
+    }
+
+    // Times outside [0, 1) wrap around the day.
+    assert_eq!(SkyColors::from_time_of_day(1.0), KEYFRAMES[0]);
+    assert_eq!(SkyColors::from_time_of_day(-0.25), KEYFRAMES[3]);
+    assert_eq!(SkyColors::from_time_of_day(2.5), KEYFRAMES[2]);
+}
+
+#[test]
+fn test_sky_colors_between_keyframes() {
+    let average = |a: [f32; 3], b: [f32; 3]| {
+        [
+            (a[0] + b[0]) / 2.0,
+            (a[1] + b[1]) / 2.0,
+            (a[2] + b[2]) / 2.0,
+        ]
+    };
+    let midpoint = |from: SkyColors, to: SkyColors| SkyColors {
+        horizon: average(from.horizon, to.horizon),
+        zenith: average(from.zenith, to.zenith),
+        sun: average(from.sun, to.sun),
+    };
+
+    assert_colors_eq(
+        SkyColors::from_time_of_day(0.125),
+        midpoint(KEYFRAMES[0], KEYFRAMES[1]),
+    );
+    // Between sunset and the next midnight.
+    assert_colors_eq(
+        SkyColors::from_time_of_day(0.875),
+        midpoint(KEYFRAMES[3], KEYFRAMES[0]),
+    );
+    assert_colors_eq(
+        SkyColors::from_time_of_day(-0.125),
+        midpoint(KEYFRAMES[3], KEYFRAMES[0]),
+    );
+}
@@ -0,0 +1,278 @@
+use super::{block_color, Chunk, Vertex, AIR};
+
+/// Density from which a point is considered inside the surface.
+const ISO_LEVEL: f32 = 0.5;
+
+// Corner pairs of the 12 cube edges, corners being indexed by their `zyx` bits.
+const CUBE_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MeshingMode {
+    Blocky,
+    /// Surface Nets. Lower levels of detail have no skirts, so seams can show
+    /// between chunks, and `raycast_blocks` still selects whole blocks.
+    Smooth,
+}
+
+/// Density sampled at every block corner of a chunk, from 0 (air) to 1 (solid).
+pub struct DensityField {
+    size: usize,
+    densities: Vec<f32>,
+}
+
+impl DensityField {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            densities: vec![0.0; size * size * size],
+        }
+    }
+
+    /// Samples a chunk of `n` blocks into `n + 1` corners per axis, each
+    /// corner taking the fraction of solid blocks around it.
+    pub fn from_chunk(chunk: &Chunk) -> Self {
+        let chunk_size = chunk.get_size() as isize;
+        let mut field = Self::new(chunk.get_size() + 1);
+
+        for z in 0..=chunk_size {
+            for y in 0..=chunk_size {
+                for x in 0..=chunk_size {
+                    let mut solid_count = 0;
+
+                    for corner in 0..8 {
+                        let block_x = x - 1 + (corner & 1) as isize;
+                        let block_y = y - 1 + ((corner >> 1) & 1) as isize;
+                        let block_z = z - 1 + ((corner >> 2) & 1) as isize;
+                        let inside = (0..chunk_size).contains(&block_x)
+                            && (0..chunk_size).contains(&block_y)
+                            && (0..chunk_size).contains(&block_z);
+
+                        if inside
+                            && chunk.get(block_x as usize, block_y as usize, block_z as usize)
+                                != AIR
+                        {
+                            solid_count += 1;
+                        }
+                    }
+
+                    field.set(x as usize, y as usize, z as usize, solid_count as f32 / 8.0);
+                }
+            }
+        }
+
+        field
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.densities[(z * self.size + y) * self.size + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, density: f32) {
+        self.densities[(z * self.size + y) * self.size + x] = density;
+    }
+}
+
+fn nearest_block_color(chunk: &Chunk, x: usize, y: usize, z: usize) -> [f32; 3] {
+    let size = chunk.get_size() as isize;
+
+    for dz in 0..2 {
+        for dy in 0..2 {
+            for dx in 0..2 {
+                let block_x = x as isize - dx;
+                let block_y = y as isize - dy;
+                let block_z = z as isize - dz;
+
+                if (0..size).contains(&block_x)
+                    && (0..size).contains(&block_y)
+                    && (0..size).contains(&block_z)
+                {
+                    let block = chunk.get(block_x as usize, block_y as usize, block_z as usize);
+
+                    if block != AIR {
+                        return block_color(block);
+                    }
+                }
+            }
+        }
+    }
+
+    block_color(AIR)
+}
+
+/// Extracts a smooth surface from `field` with Surface Nets: one vertex per
+/// cell crossing the surface, placed at the average of its edge crossings,
+/// and one quad per grid edge crossing it.
+pub fn build_surface_nets_mesh(
+    field: &DensityField,
+    chunk: &Chunk,
+    scale: f32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let cells = field.get_size() - 1;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut cell_vertices: Vec<Option<u32>> = vec![None; cells * cells * cells];
+    let cell_index = |x: usize, y: usize, z: usize| (z * cells + y) * cells + x;
+
+    for z in 0..cells {
+        for y in 0..cells {
+            for x in 0..cells {
+                let mut corners = [0.0; 8];
+
+                for (corner, density) in corners.iter_mut().enumerate() {
+                    *density = field.get(
+                        x + (corner & 1),
+                        y + ((corner >> 1) & 1),
+                        z + ((corner >> 2) & 1),
+                    );
+                }
+
+                let inside_count = corners.iter().filter(|&&d| d >= ISO_LEVEL).count();
+
+                if inside_count == 0 || inside_count == 8 {
+                    continue;
+                }
+
+                let mut sum = [0.0; 3];
+                let mut crossings = 0.0;
+
+                for &(a, b) in &CUBE_EDGES {
+                    if (corners[a] >= ISO_LEVEL) == (corners[b] >= ISO_LEVEL) {
+                        continue;
+                    }
+
+                    let t = (ISO_LEVEL - corners[a]) / (corners[b] - corners[a]);
+
+                    for axis in 0..3 {
+                        let from = ((a >> axis) & 1) as f32;
+                        let to = ((b >> axis) & 1) as f32;
+                        sum[axis] += from + t * (to - from);
+                    }
+                    crossings += 1.0;
+                }
+
+                // The density grows towards the inside, the normal points against its gradient.
+                let mut gradient = [0.0; 3];
+                for (corner, density) in corners.iter().enumerate() {
+                    for (axis, value) in gradient.iter_mut().enumerate() {
+                        let sign = if (corner >> axis) & 1 == 1 { 1.0 } else { -1.0 };
+                        *value += sign * density;
+                    }
+                }
+                let length = (gradient[0] * gradient[0]
+                    + gradient[1] * gradient[1]
+                    + gradient[2] * gradient[2])
+                    .sqrt()
+                    .max(std::f32::EPSILON);
+
+                cell_vertices[cell_index(x, y, z)] = Some(vertices.len() as u32);
+                vertices.push(Vertex::with_normal(
+                    [
+                        (x as f32 + sum[0] / crossings) * scale,
+                        (y as f32 + sum[1] / crossings) * scale,
+                        (z as f32 + sum[2] / crossings) * scale,
+                    ],
+                    [
+                        -gradient[0] / length,
+                        -gradient[1] / length,
+                        -gradient[2] / length,
+                    ],
+                    nearest_block_color(chunk, x, y, z),
+                ));
+            }
+        }
+    }
+
+    // Each grid edge crossing the surface is shared by four cells, joined into a quad.
+    for z in 0..cells {
+        for y in 0..cells {
+            for x in 0..cells {
+                let inside = field.get(x, y, z) >= ISO_LEVEL;
+                let point = [x, y, z];
+
+                for axis in 0..3 {
+                    let u = (axis + 1) % 3;
+                    let v = (axis + 2) % 3;
+                    let mut next = point;
+                    next[axis] += 1;
+
+                    if point[u] == 0
+                        || point[v] == 0
+                        || (field.get(next[0], next[1], next[2]) >= ISO_LEVEL) == inside
+                    {
+                        continue;
+                    }
+
+                    let quad_cells = [(1, 1), (0, 1), (0, 0), (1, 0)]
+                        .iter()
+                        .map(|&(du, dv)| {
+                            let mut cell = point;
+                            cell[u] -= du;
+                            cell[v] -= dv;
+                            cell_vertices[cell_index(cell[0], cell[1], cell[2])]
+                        })
+                        .collect::<Option<Vec<u32>>>();
+
+                    if let Some(quad) = quad_cells {
+                        let quad = if inside {
+                            [quad[0], quad[1], quad[2], quad[3]]
+                        } else {
+                            [quad[0], quad[3], quad[2], quad[1]]
+                        };
+
+                        indices.extend_from_slice(&[
+                            quad[0], quad[1], quad[2], quad[2], quad[3], quad[0],
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+#[test]
+fn test_flat_floor_surface() {
+    let mut chunk = Chunk::new(4);
+
+    for z in 0..4 {
+        for y in 0..2 {
+            for x in 0..4 {
+                chunk.set(x, y, z, 1);
+            }
+        }
+    }
+
+    let field = DensityField::from_chunk(&chunk);
+    let (vertices, indices) = build_surface_nets_mesh(&field, &chunk, 1.0);
+
+    assert_eq!(field.get_size(), 5);
+    assert!(!indices.is_empty());
+    assert_eq!(indices.len() % 6, 0);
+
+    // Away from the chunk border the surface lies flat on top of the blocks.
+    let inner = vertices.iter().filter(|vertex| {
+        (1.0..3.0).contains(&vertex.pos[0]) && (1.0..3.0).contains(&vertex.pos[2])
+    });
+    for vertex in inner {
+        assert_eq!(vertex.pos[1], 2.0);
+        assert_eq!(vertex.normal, [0.0, 1.0, 0.0]);
+    }
+}
@@ -0,0 +1,209 @@
+use super::{FrameLocals, SkyColors};
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+const DEFAULT_DAY_LENGTH_SECS: u64 = 10 * 60;
+const MIN_AMBIENT_LIGHT: f32 = 0.08;
+const MAX_AMBIENT_LIGHT: f32 = 0.35;
+
+#[inline]
+fn duration_to_secs(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+#[inline]
+fn clamp01(value: f32) -> f32 {
+    value.max(0.0).min(1.0)
+}
+
+/// Cyclic time of day, from 0 (midnight) through 0.25 (sunrise), 0.5 (noon)
+/// and 0.75 (sunset) back to 1 (midnight again).
+pub struct TimeOfDay {
+    time: f32,
+    day_length: Duration,
+    paused: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            time: 0.3,
+            day_length: Duration::from_secs(DEFAULT_DAY_LENGTH_SECS),
+            paused: false,
+        }
+    }
+}
+
+impl TimeOfDay {
+    pub fn new(day_length: Duration) -> Self {
+        Self {
+            day_length,
+            ..Default::default()
+        }
+    }
+
+    pub fn fixed_update(&mut self, fixed_delta_time: Duration) {
+        if self.paused {
+            return;
+        }
+
+        let day_length = duration_to_secs(self.day_length);
+
+        if day_length > 0.0 {
+            self.set_time(self.time + duration_to_secs(fixed_delta_time) / day_length);
+        }
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jumps to `time`, wrapped into the 0..1 range.
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time - time.floor();
+    }
+
+    pub fn get_day_length(&self) -> Duration {
+        self.day_length
+    }
+
+    pub fn set_day_length(&mut self, day_length: Duration) {
+        self.day_length = day_length;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Direction pointing towards the sun, rising along +X and setting along -X.
+    pub fn get_sun_direction(&self) -> Vector3<f32> {
+        let angle = (self.time - 0.25) * 2.0 * PI;
+
+        Vector3::new(angle.cos(), angle.sin(), 0.2).normalize()
+    }
+
+    pub fn get_sky_colors(&self) -> SkyColors {
+        SkyColors::from_time_of_day(self.time)
+    }
+
+    /// Ambient light level, dim at night and brightest at noon.
+    pub fn get_ambient_light(&self) -> f32 {
+        let daylight = clamp01(self.get_sun_direction().y * 2.0 + 0.2);
+
+        MIN_AMBIENT_LIGHT + (MAX_AMBIENT_LIGHT - MIN_AMBIENT_LIGHT) * daylight
+    }
+
+    /// Intensity of the direct sun light, fading out as the sun sets.
+    pub fn get_skylight_intensity(&self) -> f32 {
+        clamp01(self.get_sun_direction().y * 4.0)
+    }
+
+    /// Writes the lighting part of the frame uniforms.
+    pub fn update_frame_locals(&self, frame_locals: &mut FrameLocals) {
+        let sun_direction = self.get_sun_direction();
+        let sky_colors = self.get_sky_colors();
+        let ambient = self.get_ambient_light();
+        let skylight = self.get_skylight_intensity();
+
+        // NOTE: Ambient light is tinted by the sky, halfway between white and the zenith color.
+        let ambient_tint = |channel: f32| ambient * (1.0 + channel) * 0.5;
+
+        frame_locals.sun_direction = [sun_direction.x, sun_direction.y, sun_direction.z, 0.0];
+        frame_locals.sun_color = [
+            sky_colors.sun[0] * skylight,
+            sky_colors.sun[1] * skylight,
+            sky_colors.sun[2] * skylight,
+            1.0,
+        ];
+        frame_locals.ambient_color = [
+            ambient_tint(sky_colors.zenith[0]),
+            ambient_tint(sky_colors.zenith[1]),
+            ambient_tint(sky_colors.zenith[2]),
+            1.0,
+        ];
+    }
+}
+
+#[cfg(test)]
+fn assert_direction_eq(direction: Vector3<f32>, expected: Vector3<f32>) {
+    let expected = expected.normalize();
+
+    assert!(
+        (direction - expected).magnitude() < 1e-5,
+        "{:?} != {:?}",
+        direction,
+        expected
+    );
+}
+
+#[test]
+fn test_sun_direction() {
+    let mut time_of_day = TimeOfDay::default();
+
+    time_of_day.set_time(0.25);
+    assert_direction_eq(time_of_day.get_sun_direction(), Vector3::new(1.0, 0.0, 0.2));
+    time_of_day.set_time(0.5);
+    assert_direction_eq(time_of_day.get_sun_direction(), Vector3::new(0.0, 1.0, 0.2));
+    time_of_day.set_time(0.75);
+    assert_direction_eq(
+        time_of_day.get_sun_direction(),
+        Vector3::new(-1.0, 0.0, 0.2),
+    );
+    time_of_day.set_time(0.0);
+    assert_direction_eq(
+        time_of_day.get_sun_direction(),
+        Vector3::new(0.0, -1.0, 0.2),
+    );
+    // No direct sun light at night.
+    assert_eq!(time_of_day.get_skylight_intensity(), 0.0);
+}
+
+#[test]
+fn test_day_length_wraps_around() {
+    let mut time_of_day = TimeOfDay::new(Duration::from_secs(10));
+    time_of_day.set_time(0.75);
+
+    time_of_day.fixed_update(Duration::from_secs(5));
+    assert_eq!(time_of_day.get_time(), 0.25);
+
+    time_of_day.set_day_length(Duration::from_secs(20));
+    time_of_day.fixed_update(Duration::from_secs(5));
+    assert_eq!(time_of_day.get_time(), 0.5);
+}
+
+#[test]
+fn test_pause() {
+    let mut time_of_day = TimeOfDay::new(Duration::from_secs(10));
+    time_of_day.set_time(0.5);
+
+    time_of_day.toggle_pause();
+    time_of_day.fixed_update(Duration::from_secs(5));
+    assert!(time_of_day.is_paused());
+    assert_eq!(time_of_day.get_time(), 0.5);
+
+    time_of_day.set_paused(false);
+    time_of_day.fixed_update(Duration::from_secs(5));
+    assert_eq!(time_of_day.get_time(), 0.0);
+}
+
+#[test]
+fn test_set_time_wraps_around() {
+    let mut time_of_day = TimeOfDay::default();
+
+    time_of_day.set_time(1.5);
+    assert_eq!(time_of_day.get_time(), 0.5);
+    time_of_day.set_time(-0.25);
+    assert_eq!(time_of_day.get_time(), 0.75);
+    time_of_day.set_time(1.0);
+    assert_eq!(time_of_day.get_time(), 0.0);
+}
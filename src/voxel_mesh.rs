@@ -1,11 +1,12 @@
 use super::gfx;
-use super::{ColorFormat, Mesh, Pipeline, Renderer, Resources};
+use super::{create_vertex_buffer_with_indices, FrameLocals, Mesh, Pipeline, Renderer, Resources};
 use cgmath::Matrix4;
 use gfx::traits::FactoryExt;
 
 gfx_defines! {
     vertex Vertex {
         pos: [f32; 3] = "a_Pos",
+        normal: [f32; 3] = "a_Normal",
         color: [f32; 3] = "a_Color",
     }
 
@@ -18,19 +19,37 @@ gfx_defines! {
     pipeline pipe {
         vbuf: gfx::VertexBuffer<Vertex> = (),
         locals: gfx::ConstantBuffer<Locals> = "Locals",
-        out: gfx::RenderTarget<ColorFormat> = "Target0",
+        frame_locals: gfx::ConstantBuffer<FrameLocals> = "FrameLocals",
+        shadow_map: gfx::TextureSampler<f32> = "t_ShadowMap",
+        out: gfx::RawRenderTarget = (
+            "Target0",
+            gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Unorm),
+            gfx::state::ColorMask::all(),
+            None
+        ),
         out_depth: gfx::DepthTarget<gfx::format::DepthStencil> =
             gfx::preset::depth::LESS_EQUAL_WRITE,
     }
 }
 
 impl Vertex {
-    pub fn new(p: [i8; 3], c: [f32; 3]) -> Vertex {
+    pub fn new(p: [f32; 3], c: [f32; 3]) -> Vertex {
         Vertex {
-            pos: [f32::from(p[0]), f32::from(p[1]), f32::from(p[2])],
+            pos: p,
+            normal: [0.0, 0.0, 0.0],
             color: [c[0], c[1], c[2]],
         }
     }
+
+    /// Vertex of a smooth surface. Vertices built with `new` have no normal
+    /// and are shaded flat from their face instead.
+    pub fn with_normal(p: [f32; 3], n: [f32; 3], c: [f32; 3]) -> Vertex {
+        Vertex {
+            pos: p,
+            normal: n,
+            color: c,
+        }
+    }
 }
 
 pub struct VoxelMeshPipe {
@@ -51,9 +70,20 @@ impl VoxelMeshPipe {
             )
         };
 
+        // NOTE: The scene target format is only known at runtime, it depends on HDR being enabled.
+        let init = pipe::Init {
+            out: (
+                "Target0",
+                renderer.scene.format,
+                gfx::state::ColorMask::all(),
+                None,
+            ),
+            ..pipe::new()
+        };
+
         let pso = renderer
             .factory
-            .create_pipeline_simple(&vs_code, &fs_code, pipe::new())
+            .create_pipeline_simple(&vs_code, &fs_code, init)
             .unwrap();
 
         Self { pso }
@@ -77,20 +107,46 @@ impl VoxelMesh {
     pub fn new(
         renderer: &mut Renderer,
         vertices: &[Vertex],
-        indices: &[u16],
+        indices: &[u32],
+        transform: Matrix4<f32>,
+    ) -> Self {
+        let (vbuf, slice) =
+            create_vertex_buffer_with_indices(&mut renderer.factory, vertices, indices);
+
+        Self::from_buffer(renderer, vbuf, slice, transform)
+    }
+
+    /// Mesh drawing `vertices` in order, three per triangle.
+    pub fn new_non_indexed(
+        renderer: &mut Renderer,
+        vertices: &[Vertex],
         transform: Matrix4<f32>,
     ) -> Self {
         let (vbuf, slice) = renderer
             .factory
-            .create_vertex_buffer_with_slice(vertices, indices);
+            .create_vertex_buffer_with_slice(vertices, ());
+
+        Self::from_buffer(renderer, vbuf, slice, transform)
+    }
 
+    fn from_buffer(
+        renderer: &mut Renderer,
+        vbuf: gfx::handle::Buffer<Resources, Vertex>,
+        slice: gfx::Slice<Resources>,
+        transform: Matrix4<f32>,
+    ) -> Self {
         let locals_buffer = renderer.factory.create_constant_buffer(1);
 
         let data = pipe::Data {
             vbuf,
             locals: locals_buffer,
-            out: renderer.render_target.clone(),
-            out_depth: renderer.depth_stencil.clone(),
+            frame_locals: renderer.frame_locals.clone(),
+            shadow_map: (
+                renderer.shadow_maps.resource.clone(),
+                renderer.shadow_maps.sampler.clone(),
+            ),
+            out: renderer.scene.color.clone(),
+            out_depth: renderer.scene.depth_stencil.clone(),
         };
 
         Self {
@@ -100,6 +156,10 @@ impl VoxelMesh {
         }
     }
 
+    pub fn get_transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
     pub fn update_locals(
         &mut self,
         renderer: &mut Renderer,
@@ -116,6 +176,8 @@ impl VoxelMesh {
             .encoder
             .update_buffer(&self.data.locals, &[locals], 0)
             .unwrap();
+        self.data.out = renderer.scene.color.clone();
+        self.data.out_depth = renderer.scene.depth_stencil.clone();
     }
 }
 